@@ -0,0 +1,14 @@
+// The frame constants shared by every shader, bound at set 0. The layout must match
+// `FrameConstants` in `src/vulkan/constants.rs`.
+layout(set = 0, binding = 0) uniform FrameConstants {
+    mat4 view;
+    mat4 projection;
+    mat4 viewProjection;
+    vec2 viewportSize;
+    vec2 jitter;
+    float time;
+    float deltaTime;
+    uint frameIndex;
+    uint debugFlags;
+    float alpha;
+} frame;
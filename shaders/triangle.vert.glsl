@@ -1,46 +1,40 @@
 // Tells the Vulkan driver we're using GLSL that targets Vulkan's 1.2+
 // core specification (GLSL 4.50)
 #version 450
+#extension GL_GOOGLE_include_directive : require
 
-// Declare an output color to the fragment shader at location 0
-layout(location = 0) out vec3 fragColor;
+// The per-frame constants (time, camera matrices, viewport size, ...) at set 0
+#include "include/frame.glsl"
 
-// Contains the XY positions for each vertex
-vec2 positions[3] = vec2[](
-    vec2(0.0, -0.5),    // Top center
-    vec2(0.5, 0.5),     // Bottom right
-    vec2(-0.5, 0.5)     // Bottom left
-);
+// The per-draw transform pushed as `ObjectConstants`
+layout(push_constant) uniform ObjectConstants {
+    mat4 model;
+} object;
 
-// Contians the RGB colors for each vertex
-vec3 colors[3] = vec3[](
-    vec3(0.1, 0.4, 1.0),
-    vec3(0.2, 1.0, 0.2),
-    vec3(0.3, 0.5, 1.0)
-);
+// The vertex attributes of `ColorVertex`, read from the vertex buffer bound at binding 0
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec3 inColor;
 
-/*
-     - `gl_VertexIndex` is a built-in variable that tells the vertex shader which
-     vertex it's currently processing. This index is provided by the GPU when a
-     Vulkan draw command like vkCmdDraw() is called.
+// Declare an output color to the fragment shader at location 0
+layout(location = 0) out vec3 fragColor;
 
+/*
      - `gl_Position` is a built-in vec4 variable that the vertex shader MUST write
      to. It tells the GPU where to draw each vertex on the screen after all math and
      transformations operations are done.
 
      - `main()` runs once per vertex in the vertex shader concurrently on the GPU. This
      makes the shader *extremely* fast. Each main() call here is only different based on the
-     current vertex being executed.
+     vertex attributes it is given.
 */
 
 void main() {
-    // Set gl_Position to the current vertex in `positions`. The z coordinate is 0.0
-    // because we are rendering a 2D triangle. The w coordinate is 1.0 so perspective division
-    // holds no affect.
-    gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
-
-    // Set the fragColor ouptut to the fragment shader to a element in `colors`
-    // based on the current vertex index. This will cause color interpolation so the entire
-    // triangle can be different colors.
-    fragColor = colors[gl_VertexIndex];
-}
\ No newline at end of file
+    // Set gl_Position to the vertex position, transformed by the object and the camera. The z
+    // coordinate is 0.0 because we are rendering a 2D triangle. The w coordinate is 1.0 so
+    // perspective division holds no affect until a projection is set.
+    gl_Position = frame.viewProjection * object.model * vec4(inPosition, 0.0, 1.0);
+
+    // Pass the vertex color on to the fragment shader. This will cause color interpolation so
+    // the entire triangle can be different colors.
+    fragColor = inColor;
+}
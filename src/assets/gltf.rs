@@ -0,0 +1,276 @@
+//! glTF 2.0 scene import.
+
+use std::path::Path;
+
+use ::gltf::{
+    image::Format,
+    material::AlphaMode as GltfAlphaMode,
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, WrappingMode},
+};
+use anyhow::{Result, anyhow};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+use crate::{
+    scene::{AlphaMode, Material, Mesh, Node, Primitive, Scene, TextureData},
+    vulkan::{MeshVertex, SamplerDesc},
+};
+
+/// Imports the meshes, materials, textures and node transforms of a `.gltf` or `.glb` file.
+///
+/// Only triangle list primitives are imported. Missing normals, texture coordinates and vertex
+/// colors default to `+Z`, zero and white, and primitives without indices are indexed in order.
+pub fn load(path: impl AsRef<Path>) -> Result<Scene> {
+    let path = path.as_ref();
+    let (document, buffers, images) = ::gltf::import(path)
+        .map_err(|e| anyhow!("Failed to import glTF `{}`: {e}", path.display()))?;
+
+    // Textures
+
+    let mut textures = document
+        .textures()
+        .map(|t| {
+            let image = &images[t.source().index()];
+            Ok(TextureData {
+                width: image.width,
+                height: image.height,
+                pixels: to_rgba8(image)?,
+                srgb: false,
+                sampler: sampler_desc(&t.sampler()),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Materials
+
+    let materials = document
+        .materials()
+        .map(|m| {
+            let pbr = m.pbr_metallic_roughness();
+            Material {
+                name: m.name().map(String::from),
+                base_color_factor: Vec4::from(pbr.base_color_factor()),
+                base_color_texture: pbr.base_color_texture().map(|t| t.texture().index()),
+                metallic_factor: pbr.metallic_factor(),
+                roughness_factor: pbr.roughness_factor(),
+                metallic_roughness_texture: pbr
+                    .metallic_roughness_texture()
+                    .map(|t| t.texture().index()),
+                normal_texture: m.normal_texture().map(|t| t.texture().index()),
+                emissive_factor: Vec3::from(m.emissive_factor()),
+                emissive_texture: m.emissive_texture().map(|t| t.texture().index()),
+                alpha_mode: match m.alpha_mode() {
+                    GltfAlphaMode::Opaque => AlphaMode::Opaque,
+                    GltfAlphaMode::Mask => AlphaMode::Mask,
+                    GltfAlphaMode::Blend => AlphaMode::Blend,
+                },
+                alpha_cutoff: m.alpha_cutoff().unwrap_or(0.5),
+                double_sided: m.double_sided(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Color textures are sRGB encoded, everything else is linear data.
+    for material in &materials {
+        for texture in [material.base_color_texture, material.emissive_texture]
+            .into_iter()
+            .flatten()
+        {
+            textures[texture].srgb = true;
+        }
+    }
+
+    // Meshes
+
+    let meshes = document
+        .meshes()
+        .map(|m| {
+            let primitives = m
+                .primitives()
+                .filter(|p| {
+                    let triangles = p.mode() == Mode::Triangles;
+                    if !triangles {
+                        warn!(
+                            "Skipping {:?} primitive {} of mesh {} in `{}`.",
+                            p.mode(),
+                            p.index(),
+                            m.index(),
+                            path.display(),
+                        );
+                    }
+                    triangles
+                })
+                .map(|p| {
+                    let reader = p.reader(|b| buffers.get(b.index()).map(|d| d.0.as_slice()));
+
+                    let positions = reader
+                        .read_positions()
+                        .ok_or_else(|| anyhow!("Missing positions in mesh {}.", m.index()))?
+                        .collect::<Vec<_>>();
+                    let mut normals = reader.read_normals();
+                    let mut tex_coords = reader.read_tex_coords(0).map(|t| t.into_f32());
+                    let mut colors = reader.read_colors(0).map(|c| c.into_rgba_f32());
+
+                    // Interleave the separate attribute streams into one vertex.
+                    let vertices = positions
+                        .iter()
+                        .map(|p| MeshVertex {
+                            position: Vec3::from(*p),
+                            normal: normals
+                                .as_mut()
+                                .and_then(Iterator::next)
+                                .map_or(Vec3::Z, Vec3::from),
+                            tex_coord: tex_coords
+                                .as_mut()
+                                .and_then(Iterator::next)
+                                .map_or(Vec2::ZERO, Vec2::from),
+                            color: colors
+                                .as_mut()
+                                .and_then(Iterator::next)
+                                .map_or(Vec4::ONE, Vec4::from),
+                        })
+                        .collect::<Vec<_>>();
+
+                    let indices = match reader.read_indices() {
+                        Some(indices) => indices.into_u32().collect(),
+                        None => (0..vertices.len() as u32).collect(),
+                    };
+
+                    Ok(Primitive {
+                        vertices,
+                        indices,
+                        material: p.material().index(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(Mesh {
+                name: m.name().map(String::from),
+                primitives,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Nodes
+
+    let mut nodes = vec![];
+    if let Some(scene) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        for node in scene.nodes() {
+            add_node(&mut nodes, &node, Mat4::IDENTITY);
+        }
+    }
+
+    Ok(Scene {
+        meshes,
+        materials,
+        textures,
+        nodes,
+    })
+}
+
+/// Adds a node and its children with their transforms in world space.
+fn add_node(nodes: &mut Vec<Node>, node: &::gltf::Node, parent: Mat4) {
+    let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+
+    nodes.push(Node {
+        name: node.name().map(String::from),
+        transform,
+        mesh: node.mesh().map(|m| m.index()),
+    });
+
+    for child in node.children() {
+        add_node(nodes, &child, transform);
+    }
+}
+
+/// Converts the pixels of an image to 8-bit RGBA, with missing channels set to zero and alpha set
+/// to opaque.
+fn to_rgba8(image: &::gltf::image::Data) -> Result<Vec<u8>> {
+    let (channels, size) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    let expected = image.width as usize * image.height as usize * channels * size;
+    if image.pixels.len() != expected {
+        return Err(anyhow!(
+            "Invalid glTF image size ({} bytes, expected {expected}).",
+            image.pixels.len()
+        ));
+    }
+
+    let channel = |bytes: &[u8]| match bytes.len() {
+        1 => bytes[0],
+        2 => (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+        _ => {
+            let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        }
+    };
+
+    Ok(image
+        .pixels
+        .chunks_exact(channels * size)
+        .flat_map(|pixel| {
+            let mut rgba = [0, 0, 0, u8::MAX];
+            for (c, bytes) in pixel.chunks_exact(size).enumerate() {
+                rgba[c] = channel(bytes);
+            }
+            rgba
+        })
+        .collect())
+}
+
+/// Maps a glTF sampler to a sampler description.
+///
+/// Vulkan samplers here share one address mode, so the `S` wrapping mode is used for both axes.
+fn sampler_desc(sampler: &::gltf::texture::Sampler) -> SamplerDesc {
+    let default = SamplerDesc::default();
+
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => vk::Filter::NEAREST,
+        Some(MagFilter::Linear) => vk::Filter::LINEAR,
+        None => default.mag_filter,
+    };
+
+    let (min_filter, mipmap_mode) = match sampler.min_filter() {
+        Some(MinFilter::Nearest | MinFilter::NearestMipmapNearest) => {
+            (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
+        }
+        Some(MinFilter::Linear | MinFilter::LinearMipmapNearest) => {
+            (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST)
+        }
+        Some(MinFilter::NearestMipmapLinear) => {
+            (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR)
+        }
+        Some(MinFilter::LinearMipmapLinear) => (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR),
+        None => (default.min_filter, default.mipmap_mode),
+    };
+
+    let address_mode = match sampler.wrap_s() {
+        WrappingMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+        WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        WrappingMode::Repeat => vk::SamplerAddressMode::REPEAT,
+    };
+
+    SamplerDesc {
+        mag_filter,
+        min_filter,
+        mipmap_mode,
+        address_mode,
+        ..default
+    }
+}
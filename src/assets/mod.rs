@@ -0,0 +1,3 @@
+//! Importers that turn asset files into the renderer's [`Scene`](crate::scene::Scene).
+
+pub mod gltf;
@@ -0,0 +1,263 @@
+//! Controllers that move a [`PerspectiveCamera`] from the [`InputState`].
+
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{EulerRot, Quat, Vec3};
+use log::*;
+use winit::{
+    event::MouseButton,
+    keyboard::KeyCode,
+    window::{CursorGrabMode, Window as WinitWindow},
+};
+
+use super::PerspectiveCamera;
+use crate::{input::InputState, window::grab_cursor};
+
+/// The largest pitch, just short of straight up or down where yaw becomes ambiguous.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Returns the yaw and pitch of a rotation.
+fn yaw_pitch(rotation: Quat) -> (f32, f32) {
+    let (yaw, pitch, _) = rotation.to_euler(EulerRot::YXZ);
+    (yaw, pitch)
+}
+
+/// Returns the rotation for a yaw and pitch, without roll.
+fn rotation(yaw: f32, pitch: f32) -> Quat {
+    Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0)
+}
+
+/// Returns 1, -1 or 0 depending on which of two opposing keys is held.
+fn axis(input: &InputState, positive: KeyCode, negative: KeyCode) -> f32 {
+    input.is_key_pressed(positive) as i32 as f32 - input.is_key_pressed(negative) as i32 as f32
+}
+
+//================================================
+// First Person
+//================================================
+
+/// Flies a camera with WASD (Space and Left Shift for up and down) and looks around while the
+/// right mouse button is held, which also grabs and hides the cursor.
+#[derive(Copy, Clone, Debug)]
+pub struct FpsController {
+    /// The movement speed in world units per second.
+    pub speed: f32,
+    /// The rotation per unit of raw mouse motion in radians.
+    pub sensitivity: f32,
+    yaw: f32,
+    pitch: f32,
+    looking: bool,
+}
+
+impl Default for FpsController {
+    fn default() -> Self {
+        Self::new(&PerspectiveCamera::default())
+    }
+}
+
+impl FpsController {
+    /// Creates a controller that continues from the orientation of a camera.
+    pub fn new(camera: &PerspectiveCamera) -> Self {
+        let (yaw, pitch) = yaw_pitch(camera.rotation);
+        Self {
+            speed: 2.0,
+            sensitivity: 0.003,
+            yaw,
+            pitch,
+            looking: false,
+        }
+    }
+
+    /// Moves and rotates a camera by the input of the current frame.
+    pub fn update(
+        &mut self,
+        window: &WinitWindow,
+        input: &InputState,
+        camera: &mut PerspectiveCamera,
+        delta_time: f32,
+    ) {
+        // Look
+
+        self.set_looking(window, input.is_mouse_pressed(MouseButton::Right));
+
+        if self.looking {
+            let delta = input.mouse_motion() * self.sensitivity;
+            self.yaw -= delta.x;
+            self.pitch = (self.pitch - delta.y).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        camera.rotation = rotation(self.yaw, self.pitch);
+
+        // Move
+
+        let direction = camera.rotation * Vec3::NEG_Z * axis(input, KeyCode::KeyW, KeyCode::KeyS)
+            + camera.rotation * Vec3::X * axis(input, KeyCode::KeyD, KeyCode::KeyA)
+            + Vec3::Y * axis(input, KeyCode::Space, KeyCode::ShiftLeft);
+
+        camera.position += direction.normalize_or_zero() * self.speed * delta_time;
+    }
+
+    fn set_looking(&mut self, window: &WinitWindow, looking: bool) {
+        if self.looking == looking {
+            return;
+        }
+
+        self.looking = looking;
+
+        let mode = if looking {
+            CursorGrabMode::Locked
+        } else {
+            CursorGrabMode::None
+        };
+
+        if let Err(error) = grab_cursor(window, mode) {
+            warn!("{error:#}");
+        }
+
+        window.set_cursor_visible(!looking);
+    }
+}
+
+//================================================
+// Orbit
+//================================================
+
+/// Orbits a camera around a target point while the left mouse button is dragged and zooms with
+/// the scroll wheel.
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitController {
+    /// The point the camera orbits around and looks at.
+    pub target: Vec3,
+    /// The distance from the target to the camera.
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// The rotation per pixel of mouse movement in radians.
+    pub sensitivity: f32,
+    /// The factor the distance is multiplied by per scroll wheel line towards the target.
+    pub zoom_factor: f32,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self::new(&PerspectiveCamera::default(), Vec3::ZERO)
+    }
+}
+
+impl OrbitController {
+    /// Creates a controller that orbits a target from the current position of a camera.
+    pub fn new(camera: &PerspectiveCamera, target: Vec3) -> Self {
+        let offset = camera.position - target;
+        let direction = offset.normalize_or(Vec3::Z);
+        let yaw = direction.x.atan2(direction.z);
+        let pitch = (-direction.y).asin().clamp(-MAX_PITCH, MAX_PITCH);
+        Self {
+            target,
+            distance: offset.length().max(0.01),
+            min_distance: 0.01,
+            max_distance: 1000.0,
+            sensitivity: 0.005,
+            zoom_factor: 0.9,
+            yaw,
+            pitch,
+        }
+    }
+
+    /// Moves and rotates a camera by the input of the current frame.
+    ///
+    /// Dragging and scrolling are absolute distances, so the delta time is unused.
+    pub fn update(&mut self, input: &InputState, camera: &mut PerspectiveCamera, delta_time: f32) {
+        if input.is_mouse_pressed(MouseButton::Left) {
+            let delta = input.cursor_delta() * self.sensitivity;
+            self.yaw -= delta.x;
+            self.pitch = (self.pitch - delta.y).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        self.distance = (self.distance * self.zoom_factor.powf(input.scroll_delta().y))
+            .clamp(self.min_distance, self.max_distance);
+
+        camera.rotation = rotation(self.yaw, self.pitch);
+        camera.position = self.target + camera.rotation * Vec3::Z * self.distance;
+    }
+}
+
+//================================================
+// Camera Controller
+//================================================
+
+/// One of the camera controllers, or none to leave the camera where the application puts it.
+#[derive(Copy, Clone, Debug)]
+pub enum CameraController {
+    None,
+    Fps(FpsController),
+    Orbit(OrbitController),
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self::Orbit(OrbitController::default())
+    }
+}
+
+impl CameraController {
+    /// Moves and rotates a camera by the input of the current frame. Call it once per frame
+    /// with the duration of the frame in seconds.
+    pub fn update(
+        &mut self,
+        window: &WinitWindow,
+        input: &InputState,
+        camera: &mut PerspectiveCamera,
+        delta_time: f32,
+    ) {
+        match self {
+            Self::None => {}
+            Self::Fps(c) => c.update(window, input, camera, delta_time),
+            Self::Orbit(c) => c.update(input, camera, delta_time),
+        }
+    }
+}
+
+impl From<FpsController> for CameraController {
+    fn from(controller: FpsController) -> Self {
+        Self::Fps(controller)
+    }
+}
+
+impl From<OrbitController> for CameraController {
+    fn from(controller: OrbitController) -> Self {
+        Self::Orbit(controller)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orbit_keeps_the_starting_position() {
+        let target = Vec3::new(0.0, 1.0, 0.0);
+        let mut camera = PerspectiveCamera {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            ..PerspectiveCamera::default()
+        };
+        let mut controller = OrbitController::new(&camera, target);
+        assert!((controller.distance - 11f32.sqrt()).abs() < 1e-5);
+
+        controller.update(&InputState::new(), &mut camera, 0.1);
+        assert!(camera.position.abs_diff_eq(Vec3::new(1.0, 2.0, 3.0), 1e-5));
+        assert!(
+            camera
+                .forward()
+                .abs_diff_eq((target - camera.position).normalize(), 1e-5)
+        );
+    }
+
+    #[test]
+    fn yaw_pitch_round_trip() {
+        let (yaw, pitch) = yaw_pitch(rotation(0.7, -0.4));
+        assert!((yaw - 0.7).abs() < 1e-5);
+        assert!((pitch + 0.4).abs() < 1e-5);
+    }
+}
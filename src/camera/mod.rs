@@ -0,0 +1,208 @@
+//! Cameras that produce view and projection matrices for Vulkan's clip space.
+//!
+//! World space is right-handed with `+Y` up, and cameras look down their local `-Z` axis.
+//! Projections flip `Y` (Vulkan's clip space points down) and map depth to `0..1`.
+
+pub mod controller;
+
+use glam::{Mat4, Quat, Vec3};
+
+pub use self::controller::{CameraController, FpsController, OrbitController};
+
+/// A camera that can be passed to [`Renderer::update_camera`](crate::Renderer::update_camera).
+pub trait Camera {
+    /// Returns the matrix that transforms world space into view space.
+    fn view(&self) -> Mat4;
+
+    /// Returns the matrix that transforms view space into clip space for a viewport with the
+    /// given width to height ratio.
+    fn projection(&self, aspect_ratio: f32) -> Mat4;
+}
+
+/// Flips the `Y` axis of a right-handed, `0..1` depth projection for Vulkan's clip space.
+fn flip_y(mut projection: Mat4) -> Mat4 {
+    projection.y_axis.y = -projection.y_axis.y;
+    projection
+}
+
+/// Returns the rotation that points the local `-Z` axis from `position` towards `target`.
+fn look_rotation(position: Vec3, target: Vec3) -> Quat {
+    let view = Mat4::look_at_rh(position, target, Vec3::Y);
+    Quat::from_mat4(&view).inverse()
+}
+
+//================================================
+// Perspective
+//================================================
+
+/// A camera with a perspective projection.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PerspectiveCamera {
+    pub position: Vec3,
+    pub rotation: Quat,
+    /// The vertical field of view in radians.
+    pub fov_y: f32,
+    /// The distance to the near clipping plane.
+    pub near: f32,
+    /// The distance to the far clipping plane.
+    pub far: f32,
+}
+
+impl Default for PerspectiveCamera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 0.0, 2.0),
+            rotation: Quat::IDENTITY,
+            fov_y: 60f32.to_radians(),
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl PerspectiveCamera {
+    /// Interpolates between this camera at `t = 0` and another camera at `t = 1`.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            fov_y: self.fov_y + (other.fov_y - self.fov_y) * t,
+            ..*other
+        }
+    }
+
+    /// Rotates the camera to look at a point.
+    pub fn look_at(&mut self, target: Vec3) {
+        self.rotation = look_rotation(self.position, target);
+    }
+
+    /// Returns the direction the camera looks in.
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn view(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position).inverse()
+    }
+
+    fn projection(&self, aspect_ratio: f32) -> Mat4 {
+        flip_y(Mat4::perspective_rh(
+            self.fov_y,
+            aspect_ratio,
+            self.near,
+            self.far,
+        ))
+    }
+}
+
+//================================================
+// Orthographic
+//================================================
+
+/// A camera with an orthographic projection, for 2D and CAD style views.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrthographicCamera {
+    pub position: Vec3,
+    pub rotation: Quat,
+    /// The height of the view in world units. The width follows from the aspect ratio.
+    pub height: f32,
+    /// The distance to the near clipping plane.
+    pub near: f32,
+    /// The distance to the far clipping plane.
+    pub far: f32,
+}
+
+impl Default for OrthographicCamera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 0.0, 1.0),
+            rotation: Quat::IDENTITY,
+            height: 2.0,
+            near: 0.0,
+            far: 100.0,
+        }
+    }
+}
+
+impl OrthographicCamera {
+    /// Interpolates between this camera at `t = 0` and another camera at `t = 1`.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            height: self.height + (other.height - self.height) * t,
+            ..*other
+        }
+    }
+
+    /// Rotates the camera to look at a point.
+    pub fn look_at(&mut self, target: Vec3) {
+        self.rotation = look_rotation(self.position, target);
+    }
+
+    /// Returns the direction the camera looks in.
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn view(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position).inverse()
+    }
+
+    fn projection(&self, aspect_ratio: f32) -> Mat4 {
+        let half_height = self.height / 2.0;
+        let half_width = half_height * aspect_ratio;
+        flip_y(Mat4::orthographic_rh(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            self.near,
+            self.far,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_endpoints_and_midpoint() {
+        let a = PerspectiveCamera::default();
+        let mut b = PerspectiveCamera {
+            position: Vec3::new(2.0, 0.0, 0.0),
+            fov_y: 90f32.to_radians(),
+            far: 50.0,
+            ..a
+        };
+        b.look_at(Vec3::ZERO);
+
+        assert_eq!(a.lerp(&b, 0.0).position, a.position);
+        assert!(a.lerp(&b, 1.0).forward().abs_diff_eq(b.forward(), 1e-5));
+
+        let mid = a.lerp(&b, 0.5);
+        assert!(mid.position.abs_diff_eq(Vec3::new(1.0, 0.0, 1.0), 1e-6));
+        assert!((mid.fov_y - 75f32.to_radians()).abs() < 1e-6);
+        assert!(mid.rotation.is_normalized());
+        assert_eq!(mid.far, 50.0);
+    }
+
+    #[test]
+    fn look_at_points_forward() {
+        let mut camera = PerspectiveCamera {
+            position: Vec3::new(3.0, 4.0, 5.0),
+            ..PerspectiveCamera::default()
+        };
+        camera.look_at(Vec3::ZERO);
+        assert!(
+            camera
+                .forward()
+                .abs_diff_eq(-camera.position.normalize(), 1e-5)
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Crash reports written to disk when the application panics.
+
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use log::{LevelFilter, Log, Metadata, Record};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::vulkan::Context;
+
+/// The number of recent log lines included in a crash report.
+const LOG_HISTORY_LINES: usize = 200;
+
+/// The least severe level kept for crash reports, even when `RUST_LOG` prints less.
+const LOG_HISTORY_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// The directory next to the executable that crash reports are written to.
+const CRASH_REPORT_DIR: &str = "crash-reports";
+
+/// The most recent log lines, oldest first.
+static LOG_HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// A JSON description of the GPU in use, recorded once a context has been created.
+static GPU_REPORT: Mutex<Option<String>> = Mutex::new(None);
+
+/// A description of the current render settings, recorded once a context has been created.
+static RENDER_SETTINGS: Mutex<Option<String>> = Mutex::new(None);
+
+/// Initializes logging and installs a panic hook that writes a crash report before the default
+/// panic handling runs. Reports are written to a `crash-reports` directory next to the
+/// executable, and their full path is printed to stderr.
+///
+/// Logging to stderr is configured through `RUST_LOG`, the same as `pretty_env_logger::init`.
+/// Crash reports include the recent log lines down to `info` regardless.
+pub fn install() -> Result<()> {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+
+    let logger = builder.build();
+    log::set_max_level(logger.filter().max(LOG_HISTORY_LEVEL));
+    log::set_boxed_logger(Box::new(HistoryLogger {
+        inner: Box::new(logger),
+    }))?;
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Ok(path) => eprintln!("Crash report written to `{}`.", path.display()),
+            Err(error) => eprintln!("Failed to write crash report: {error}"),
+        }
+
+        default_hook(info);
+    }));
+
+    Ok(())
+}
+
+/// Records the GPU and render settings of a context to include in crash reports.
+pub(crate) unsafe fn record_context(context: &Context) {
+    let properties = context
+        .instance
+        .get_physical_device_properties(context.data.physical_device);
+
+    let gpu = format!(
+        "{{\"name\": \"{}\", \"type\": \"{:?}\", \"vendor_id\": {}, \"device_id\": {}, \
+         \"api_version\": \"{}\", \"driver_version\": {}}}",
+        escape_json(&properties.device_name.to_string_lossy()),
+        properties.device_type,
+        properties.vendor_id,
+        properties.device_id,
+        vulkanalia::Version::from(properties.api_version),
+        properties.driver_version,
+    );
+
+    let settings = format!(
+        "swapchain_format: {:?}\nswapchain_extent: {}x{}\nrender_extent: {}x{} (scale {})\n\
+         present_mode: {:?} (preferred {:?})\nmsaa_samples: {}\nframes_in_flight: {}\nvalidation: {}",
+        context.data.swapchain_format,
+        context.data.swapchain_extent.width,
+        context.data.swapchain_extent.height,
+        context.data.render_extent.width,
+        context.data.render_extent.height,
+        context.data.render_scale,
+        context.data.swapchain_present_mode,
+        context.data.present_mode_preference,
+        context.data.msaa_samples.bits(),
+        context.data.frames.len(),
+        context.data.debug.enabled,
+    );
+
+    *GPU_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(gpu);
+    *RENDER_SETTINGS.lock().unwrap_or_else(|e| e.into_inner()) = Some(settings);
+}
+
+/// Writes a crash report for a panic, returning the path of the report.
+fn write_report(info: &PanicHookInfo) -> Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let mut report = String::new();
+    writeln!(report, "Vulkan-RS crash report ({timestamp})")?;
+    writeln!(report, "\n== Panic ==\n{info}")?;
+    writeln!(report, "\n== Backtrace ==\n{}", Backtrace::force_capture())?;
+
+    // Never block here; the panic may have happened while one of these locks was held.
+    let gpu = GPU_REPORT.try_lock().ok().and_then(|g| g.clone());
+    let settings = RENDER_SETTINGS.try_lock().ok().and_then(|s| s.clone());
+    let unavailable = "(unavailable)".to_string();
+    writeln!(
+        report,
+        "== GPU ==\n{}",
+        gpu.unwrap_or_else(|| unavailable.clone())
+    )?;
+    writeln!(
+        report,
+        "\n== Render Settings ==\n{}",
+        settings.unwrap_or(unavailable)
+    )?;
+
+    writeln!(report, "\n== Log ==")?;
+    if let Ok(history) = LOG_HISTORY.try_lock() {
+        history.iter().try_for_each(|l| writeln!(report, "{l}"))?;
+    }
+
+    let dir = crash_report_dir();
+    fs::create_dir_all(&dir)?;
+    let path = create_report_file(&dir, timestamp, report.as_bytes())?;
+
+    Ok(fs::canonicalize(&path).unwrap_or(path))
+}
+
+/// Writes a report to a new file in a directory, named after the timestamp and the process id.
+/// Panics on several threads within the same second get a numbered suffix instead of
+/// overwriting each other.
+fn create_report_file(dir: &Path, timestamp: u64, report: &[u8]) -> Result<PathBuf> {
+    let name = format!("crash-{timestamp}-{}", process::id());
+
+    let mut path = dir.join(format!("{name}.txt"));
+    let mut attempt = 1;
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(report)?;
+                return Ok(path);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                path = dir.join(format!("{name}-{attempt}.txt"));
+                attempt += 1;
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+/// Returns the directory crash reports are written to, which is next to the executable so it
+/// does not depend on the working directory the application was started from.
+fn crash_report_dir() -> PathBuf {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    // Fall back to the working directory if the executable cannot be located.
+    let base = exe_dir
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+
+    base.join(CRASH_REPORT_DIR)
+}
+
+/// Escapes a string for use inside a JSON string literal.
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A logger that forwards to another logger while keeping the most recent lines for crash reports.
+/// The history keeps lines down to [`LOG_HISTORY_LEVEL`] even when the other logger filters them.
+struct HistoryLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for HistoryLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= LOG_HISTORY_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if let Ok(mut history) = LOG_HISTORY.lock() {
+            if history.len() == LOG_HISTORY_LINES {
+                history.pop_front();
+            }
+            history.push_back(format!(
+                "{:<5} {} > {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_in_the_same_second_do_not_overwrite() {
+        let dir = std::env::temp_dir().join(format!("vulkanrs-crash-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let first = create_report_file(&dir, 42, b"first").unwrap();
+        let second = create_report_file(&dir, 42, b"second").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&first).unwrap(), b"first");
+        assert_eq!(fs::read(&second).unwrap(), b"second");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+//! Gamepad input through `gilrs`, merged into the [`InputState`].
+//!
+//! Only built with the `gamepad` feature, since `gilrs` links against `libudev` on Linux.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+use log::*;
+use winit::event::ElementState;
+
+use super::{Buttons, InputState};
+
+/// The default fraction of an axis around its center that is treated as zero.
+pub const DEFAULT_DEADZONE: f32 = 0.15;
+
+/// The largest deadzone, which still leaves part of each axis to rescale to the full range.
+pub const MAX_DEADZONE: f32 = 0.95;
+
+/// A gamepad being connected or disconnected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+}
+
+/// The state of a connected gamepad.
+#[derive(Clone, Debug, Default)]
+pub struct GamepadState {
+    name: String,
+    buttons: Buttons<Button>,
+    axes: HashMap<Axis, f32>,
+}
+
+impl GamepadState {
+    /// Returns the name of the gamepad, from its mapping if it has one.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether a button is held down.
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.buttons.held.contains(&button)
+    }
+
+    /// Returns whether a button was pressed this frame.
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.buttons.pressed.contains(&button)
+    }
+
+    /// Returns whether a button was released this frame.
+    pub fn just_released(&self, button: Button) -> bool {
+        self.buttons.released.contains(&button)
+    }
+
+    /// Returns the value of an axis from -1 to 1, which is 0 inside the deadzone.
+    pub fn axis(&self, axis: Axis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// Returns whether any button is held or any axis is outside the deadzone.
+    pub fn any_pressed(&self) -> bool {
+        !self.buttons.held.is_empty() || self.axes.values().any(|v| *v != 0.0)
+    }
+
+    /// Returns whether buttons were pressed or released since the input was last consumed.
+    pub(super) fn has_pending(&self) -> bool {
+        !self.buttons.pressed.is_empty() || !self.buttons.released.is_empty()
+    }
+
+    pub(super) fn end_frame(&mut self) {
+        self.buttons.end_frame();
+    }
+}
+
+/// Polls connected gamepads and feeds their events into an [`InputState`].
+#[derive(Debug)]
+pub struct Gamepads {
+    gilrs: Gilrs,
+    deadzone: f32,
+}
+
+impl Gamepads {
+    /// Starts listening for gamepads.
+    pub fn new() -> Result<Self> {
+        let gilrs = Gilrs::new().map_err(|e| anyhow!("Failed to initialize gamepads: {e}"))?;
+        Ok(Self {
+            gilrs,
+            deadzone: DEFAULT_DEADZONE,
+        })
+    }
+
+    /// Returns the fraction of an axis around its center that is treated as zero.
+    pub fn deadzone(&self) -> f32 {
+        self.deadzone
+    }
+
+    /// Sets the fraction of an axis around its center that is treated as zero, clamped to 0 to
+    /// [`MAX_DEADZONE`]. The remaining range is rescaled so axes still reach -1 and 1.
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = clamp_deadzone(deadzone);
+    }
+
+    /// Adds connected gamepads that the input state does not know about yet, and applies every
+    /// event since the previous call.
+    pub fn poll(&mut self, input: &mut InputState) {
+        for (id, gamepad) in self.gilrs.gamepads() {
+            if !input.gamepads.contains_key(&id) {
+                self.connect(input, id, gamepad.name());
+            }
+        }
+
+        while let Some(event) = self.gilrs.next_event() {
+            let id = event.id;
+            match event.event {
+                EventType::Connected => {
+                    let name = self.gilrs.gamepad(id).name().to_string();
+                    self.connect(input, id, &name);
+                }
+                EventType::Disconnected => {
+                    if input.gamepads.remove(&id).is_some() {
+                        info!("Disconnected gamepad {id}.");
+                        input.gamepad_events.push(GamepadEvent::Disconnected(id));
+                    }
+                }
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(gamepad) = input.gamepads.get_mut(&id) {
+                        gamepad.buttons.set(button, ElementState::Pressed);
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(gamepad) = input.gamepads.get_mut(&id) {
+                        gamepad.buttons.set(button, ElementState::Released);
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(gamepad) = input.gamepads.get_mut(&id) {
+                        gamepad
+                            .axes
+                            .insert(axis, apply_deadzone(value, self.deadzone));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn connect(&self, input: &mut InputState, id: GamepadId, name: &str) {
+        if input.gamepads.contains_key(&id) {
+            return;
+        }
+
+        info!("Connected gamepad {id} (`{name}`).");
+        input.gamepads.insert(
+            id,
+            GamepadState {
+                name: name.to_string(),
+                ..GamepadState::default()
+            },
+        );
+        input.gamepad_events.push(GamepadEvent::Connected(id));
+    }
+}
+
+/// Clamps a deadzone to 0 to [`MAX_DEADZONE`], so rescaling never divides by zero. NaN becomes 0.
+fn clamp_deadzone(deadzone: f32) -> f32 {
+    if deadzone.is_nan() {
+        0.0
+    } else {
+        deadzone.clamp(0.0, MAX_DEADZONE)
+    }
+}
+
+/// Zeroes an axis value inside the deadzone and rescales the rest to the full range.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone() {
+        assert_eq!(apply_deadzone(0.1, 0.15), 0.0);
+        assert_eq!(apply_deadzone(-0.15, 0.15), 0.0);
+        assert_eq!(apply_deadzone(1.0, 0.15), 1.0);
+        assert_eq!(apply_deadzone(-1.0, 0.15), -1.0);
+        assert!((apply_deadzone(0.575, 0.15) - 0.5).abs() < 1e-6);
+        assert!((apply_deadzone(-0.575, 0.15) + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn deadzone_is_clamped() {
+        assert_eq!(clamp_deadzone(0.2), 0.2);
+        assert_eq!(clamp_deadzone(-1.0), 0.0);
+        assert_eq!(clamp_deadzone(1.0), MAX_DEADZONE);
+        assert_eq!(clamp_deadzone(f32::NAN), 0.0);
+        assert_eq!(apply_deadzone(1.0, clamp_deadzone(2.0)), 1.0);
+    }
+}
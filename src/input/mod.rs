@@ -0,0 +1,277 @@
+//! Keyboard, mouse and gamepad state collected from window and device events.
+
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+
+#[cfg(feature = "gamepad")]
+use std::collections::HashMap;
+use std::{collections::HashSet, hash::Hash};
+
+#[cfg(feature = "gamepad")]
+use gilrs::GamepadId;
+use glam::Vec2;
+use winit::{
+    event::{DeviceEvent, ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+/// The scroll distance of one wheel line in pixels, for touchpads that scroll by pixels.
+const PIXELS_PER_LINE: f32 = 40.0;
+
+/// The held buttons of a device and the buttons that changed since the last frame.
+#[derive(Clone, Debug)]
+struct Buttons<T> {
+    held: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Self {
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    fn set(&mut self, button: T, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if self.held.insert(button) {
+                    self.pressed.insert(button);
+                }
+            }
+            ElementState::Released => {
+                if self.held.remove(&button) {
+                    self.released.insert(button);
+                }
+            }
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.held.drain());
+    }
+
+    fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+}
+
+/// The state of the keyboard and mouse, updated from window events.
+///
+/// Keys are identified by their physical location, so WASD stays in place on other layouts.
+/// The `just_*` queries cover the events since the previous call to
+/// [`InputState::clear_presses`], and the deltas the events since the previous call to
+/// [`InputState::clear_motion`].
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    keys: Buttons<KeyCode>,
+    modifiers: ModifiersState,
+    mouse_buttons: Buttons<MouseButton>,
+    cursor_position: Option<Vec2>,
+    cursor_delta: Vec2,
+    mouse_motion: Vec2,
+    scroll_delta: Vec2,
+    unfocused: bool,
+    #[cfg(feature = "gamepad")]
+    gamepads: HashMap<GamepadId, gamepad::GamepadState>,
+    #[cfg(feature = "gamepad")]
+    gamepad_events: Vec<gamepad::GamepadEvent>,
+}
+
+impl InputState {
+    /// Creates an input state with nothing pressed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state from a window event.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state,
+                        ..
+                    },
+                ..
+            } => self.keys.set(*code, *state),
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::MouseInput { button, state, .. } => {
+                self.mouse_buttons.set(*button, *state)
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Vec2::new(position.x as f32, position.y as f32);
+                if let Some(last) = self.cursor_position {
+                    self.cursor_delta += position - last;
+                }
+                self.cursor_position = Some(position);
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor_position = None,
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll_delta += match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y),
+                    MouseScrollDelta::PixelDelta(p) => {
+                        Vec2::new(p.x as f32, p.y as f32) / PIXELS_PER_LINE
+                    }
+                }
+            }
+            // Releases while unfocused never reach the window, so release everything.
+            WindowEvent::Focused(focused) => {
+                self.unfocused = !focused;
+                if !focused {
+                    self.modifiers = ModifiersState::empty();
+                    self.keys.release_all();
+                    self.mouse_buttons.release_all();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the state from a device event. Device events arrive regardless of focus, so they
+    /// are ignored while the window is unfocused.
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event
+            && !self.unfocused
+        {
+            self.mouse_motion += Vec2::new(*x as f32, *y as f32);
+        }
+    }
+
+    /// Clears the presses, releases and movement since the last call.
+    pub fn end_frame(&mut self) {
+        self.clear_presses();
+        self.clear_motion();
+    }
+
+    /// Clears the presses, releases and gamepad events once they have been consumed, which
+    /// [`Window`](crate::Window) does after every simulation tick.
+    pub fn clear_presses(&mut self) {
+        self.keys.end_frame();
+        self.mouse_buttons.end_frame();
+
+        #[cfg(feature = "gamepad")]
+        {
+            self.gamepads.values_mut().for_each(|g| g.end_frame());
+            self.gamepad_events.clear();
+        }
+    }
+
+    /// Clears the cursor, mouse and scroll movement once it has been consumed, which
+    /// [`Window`](crate::Window) does after every rendered frame.
+    pub fn clear_motion(&mut self) {
+        self.cursor_delta = Vec2::ZERO;
+        self.mouse_motion = Vec2::ZERO;
+        self.scroll_delta = Vec2::ZERO;
+    }
+
+    /// Returns whether any key, mouse button or gamepad button is held down, or a gamepad axis
+    /// is outside its deadzone.
+    pub fn any_pressed(&self) -> bool {
+        #[cfg(feature = "gamepad")]
+        if self.gamepads.values().any(|g| g.any_pressed()) {
+            return true;
+        }
+
+        !self.keys.held.is_empty() || !self.mouse_buttons.held.is_empty()
+    }
+
+    /// Returns whether there are presses, releases or movement that have not been cleared yet.
+    pub fn has_pending(&self) -> bool {
+        #[cfg(feature = "gamepad")]
+        if !self.gamepad_events.is_empty() || self.gamepads.values().any(|g| g.has_pending()) {
+            return true;
+        }
+
+        !self.keys.pressed.is_empty()
+            || !self.keys.released.is_empty()
+            || !self.mouse_buttons.pressed.is_empty()
+            || !self.mouse_buttons.released.is_empty()
+            || self.cursor_delta != Vec2::ZERO
+            || self.mouse_motion != Vec2::ZERO
+            || self.scroll_delta != Vec2::ZERO
+    }
+
+    /// Returns whether a key is held down.
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.keys.held.contains(&key)
+    }
+
+    /// Returns whether a key was pressed this frame. Key repeats are ignored.
+    pub fn key_just_pressed(&self, key: KeyCode) -> bool {
+        self.keys.pressed.contains(&key)
+    }
+
+    /// Returns whether a key was released this frame.
+    pub fn key_just_released(&self, key: KeyCode) -> bool {
+        self.keys.released.contains(&key)
+    }
+
+    /// Returns the held modifier keys.
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Returns whether a mouse button is held down.
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.held.contains(&button)
+    }
+
+    /// Returns whether a mouse button was pressed this frame.
+    pub fn mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.pressed.contains(&button)
+    }
+
+    /// Returns whether a mouse button was released this frame.
+    pub fn mouse_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.released.contains(&button)
+    }
+
+    /// Returns the cursor position in physical pixels, or `None` when it is outside the window.
+    pub fn cursor_position(&self) -> Option<Vec2> {
+        self.cursor_position
+    }
+
+    /// Returns how far the cursor moved this frame in physical pixels.
+    pub fn cursor_delta(&self) -> Vec2 {
+        self.cursor_delta
+    }
+
+    /// Returns the raw mouse movement this frame in device units, without pointer acceleration.
+    /// Unlike [`InputState::cursor_delta`], it keeps reporting while the cursor is locked or at
+    /// the edge of the screen, which suits mouse look.
+    pub fn mouse_motion(&self) -> Vec2 {
+        self.mouse_motion
+    }
+
+    /// Returns how far the scroll wheel moved this frame in lines, with positive `y` scrolling
+    /// up.
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll_delta
+    }
+
+    /// Returns the state of a connected gamepad.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad(&self, id: GamepadId) -> Option<&gamepad::GamepadState> {
+        self.gamepads.get(&id)
+    }
+
+    /// Returns the connected gamepads.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepads(&self) -> impl Iterator<Item = (GamepadId, &gamepad::GamepadState)> {
+        self.gamepads.iter().map(|(id, g)| (*id, g))
+    }
+
+    /// Returns the gamepads connected or disconnected this frame.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_events(&self) -> &[gamepad::GamepadEvent] {
+        &self.gamepad_events
+    }
+}
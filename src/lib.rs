@@ -0,0 +1,27 @@
+//! A Vulkan based renderer written in Rust.
+
+#![allow(
+    dead_code,
+    unused_variables,
+    unsafe_op_in_unsafe_fn,
+    clippy::manual_slice_size_calculation,
+    clippy::too_many_arguments,
+    clippy::unnecessary_wraps
+)]
+
+pub mod assets;
+pub mod camera;
+pub mod crash;
+pub mod input;
+pub mod renderer;
+pub mod rng;
+pub mod scene;
+pub mod time;
+pub mod vulkan;
+pub mod window;
+
+pub use self::{
+    renderer::{Renderer, RendererConfig},
+    vulkan::Context,
+    window::{FullscreenMode, RenderMode, Window, WindowConfig},
+};
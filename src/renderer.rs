@@ -0,0 +1,412 @@
+//! The renderer that draws frames into a window using a Vulkan [`Context`].
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use glam::{Mat4, Vec2};
+use vulkanalia::{prelude::v1_0::*, vk::KhrSwapchainExtension};
+use winit::window::Window as WinitWindow;
+
+use crate::{
+    camera::Camera,
+    crash,
+    rng::{Rng, get_seed},
+    time::{FrameLimiter, FramePacing},
+    vulkan::{
+        Context, DEFAULT_FRAMES_IN_FLIGHT, DebugConfig, DeviceSelector, FrameConstants,
+        MAX_RENDER_SCALE, MIN_RENDER_SCALE, PresentModePreference,
+        command::record_command_buffer,
+        sync::{get_gpu_timeout, wait_for_fence},
+    },
+};
+
+/// Options used when creating a [`Renderer`].
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    /// Picks the physical device to render with.
+    pub device_selector: DeviceSelector,
+    /// The preferred present mode, which can be changed later with [`Renderer::set_vsync`].
+    pub present_mode: PresentModePreference,
+    /// The number of frames the CPU can record while the GPU is still processing earlier ones
+    /// (usually 2 or 3).
+    pub frames_in_flight: usize,
+    /// The number of samples per pixel for MSAA (1 disables it). Clamped to the largest sample
+    /// count the device supports, and can be changed later with
+    /// [`Renderer::set_msaa_samples`].
+    pub msaa_samples: u32,
+    /// The render resolution relative to the swapchain extent. Frames are rendered at this
+    /// resolution and scaled to the swapchain, so values below 1 trade sharpness for speed and
+    /// values above 1 supersample. Clamped to 0.25 to 4, and can be changed later with
+    /// [`Renderer::set_render_scale`].
+    pub render_scale: f32,
+    /// The seed of the random streams handed out by [`Renderer::rng`], or `None` to use the
+    /// `VULKANRS_SEED` environment variable and otherwise
+    /// [`DEFAULT_SEED`](crate::rng::DEFAULT_SEED).
+    pub seed: Option<u64>,
+    /// The maximum number of frames per second, or `None` for no limit. Useful with the
+    /// immediate and mailbox present modes, which otherwise render as fast as possible.
+    pub max_fps: Option<u32>,
+    /// Controls the validation layers, read from the `VULKANRS_VALIDATION*` environment
+    /// variables by default.
+    pub debug: DebugConfig,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            device_selector: DeviceSelector::default(),
+            present_mode: PresentModePreference::default(),
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            msaa_samples: 1,
+            render_scale: 1.0,
+            seed: None,
+            max_fps: None,
+            debug: DebugConfig::from_env(),
+        }
+    }
+}
+
+/// Draws frames into a window.
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    context: Context,
+    swapchain_outdated: bool,
+    targets_outdated: bool,
+    redraw_needed: bool,
+    gpu_timeout: Duration,
+    checkpoints: Vec<String>,
+    constants: FrameConstants,
+    limiter: FrameLimiter,
+    seed: u64,
+}
+
+impl Renderer {
+    /// Creates a renderer for a window.
+    ///
+    /// # Safety
+    ///
+    /// The window must outlive the returned renderer, and [`Renderer::destroy`] must be called
+    /// before the renderer is dropped.
+    pub unsafe fn create(window: &WinitWindow, config: &RendererConfig) -> Result<Self> {
+        let context = Context::create(window, config)?;
+        crash::record_context(&context);
+
+        let seed = get_seed(config.seed);
+
+        Ok(Self {
+            checkpoints: vec![String::new(); context.data.frames.len()],
+            context,
+            swapchain_outdated: false,
+            targets_outdated: false,
+            redraw_needed: false,
+            gpu_timeout: get_gpu_timeout(),
+            constants: FrameConstants::default(),
+            limiter: FrameLimiter::new(config.max_fps),
+            seed,
+        })
+    }
+
+    /// Returns the Vulkan context used by the renderer.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Renders a frame.
+    ///
+    /// # Safety
+    ///
+    /// The renderer must not have been destroyed.
+    pub unsafe fn render(&mut self, window: &WinitWindow) -> Result<()> {
+        self.redraw_needed = false;
+
+        let frame_index = self.context.data.frames.current_index();
+        let frame = *self.context.data.frames.current();
+
+        self.wait_for_fence(frame_index)?;
+
+        // The swapchain is recreated before acquiring an image, so the frame after a resize is
+        // already drawn at the new size.
+        if self.swapchain_outdated {
+            self.swapchain_outdated = false;
+            self.targets_outdated = false;
+            self.context.recreate_swapchain(window)?;
+            crash::record_context(&self.context);
+        }
+
+        let result = self.context.device.acquire_next_image_khr(
+            self.context.data.swapchain,
+            u64::MAX,
+            frame.image_available_semaphore,
+            vk::Fence::null(),
+        );
+
+        let image_index = match result {
+            Ok((image_index, _)) => image_index as usize,
+            Err(vk::ErrorCode::OUT_OF_DATE_KHR) => return self.recreate_swapchain(window),
+            Err(e) => return Err(anyhow!(e)),
+        };
+
+        let image_in_flight = self.context.data.images_in_flight[image_index];
+        if let Some(index) = self.context.data.frames.position_of_fence(image_in_flight) {
+            self.wait_for_fence(index)?;
+        }
+
+        self.context.data.images_in_flight[image_index] = frame.in_flight_fence;
+
+        self.update_constants();
+        self.context.data.frames.constants().update(
+            &self.context.device,
+            frame_index,
+            &self.constants,
+        )?;
+
+        record_command_buffer(
+            &self.context.device,
+            &self.context.data,
+            frame_index,
+            image_index,
+        )?;
+
+        let wait_semaphores = &[frame.image_available_semaphore];
+        // The swapchain image is first written by the blit from the render target.
+        let wait_stages = &[vk::PipelineStageFlags::TRANSFER];
+        let command_buffers = &[frame.command_buffer];
+        let signal_semaphores = &[frame.render_finished_semaphore];
+        let submit_info = vk::SubmitInfo::builder()
+            .wait_semaphores(wait_semaphores)
+            .wait_dst_stage_mask(wait_stages)
+            .command_buffers(command_buffers)
+            .signal_semaphores(signal_semaphores);
+
+        self.context.device.reset_fences(&[frame.in_flight_fence])?;
+
+        self.context.device.queue_submit(
+            self.context.data.graphics_queue,
+            &[submit_info],
+            frame.in_flight_fence,
+        )?;
+
+        self.checkpoints[frame_index] =
+            format!("draw to swapchain image {image_index} (frame {frame_index})");
+
+        let swapchains = &[self.context.data.swapchain];
+        let image_indices = &[image_index as u32];
+        let present_info = vk::PresentInfoKHR::builder()
+            .wait_semaphores(signal_semaphores)
+            .swapchains(swapchains)
+            .image_indices(image_indices);
+
+        let result = self
+            .context
+            .device
+            .queue_present_khr(self.context.data.present_queue, &present_info);
+
+        let changed = match result {
+            Ok(vk::SuccessCode::SUBOPTIMAL_KHR) | Err(vk::ErrorCode::OUT_OF_DATE_KHR) => true,
+            Ok(_) => false,
+            Err(e) => return Err(anyhow!(e)),
+        };
+
+        if changed {
+            self.targets_outdated = false;
+            self.recreate_swapchain(window)?;
+        } else if self.targets_outdated {
+            self.targets_outdated = false;
+            self.context.update_render_targets()?;
+            crash::record_context(&self.context);
+            self.redraw_needed = true;
+        }
+
+        self.context.data.frames.advance();
+        self.constants.frame_index = self.constants.frame_index.wrapping_add(1);
+
+        self.limiter.wait();
+
+        Ok(())
+    }
+
+    /// Returns whether the last frame was skipped or presented at a stale size because the
+    /// swapchain or render targets were recreated, so another frame should be rendered even if
+    /// nothing else changed.
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw_needed
+    }
+
+    /// Returns the frame constants uploaded for the most recent frame.
+    pub fn frame_constants(&self) -> &FrameConstants {
+        &self.constants
+    }
+
+    /// Returns the random seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns a generator for a named random stream, which produces the same numbers for the
+    /// same seed on every run.
+    pub fn rng(&self, stream: &str) -> Rng {
+        Rng::named(self.seed, stream)
+    }
+
+    /// Sets the animation time and the animation time since the previous frame used from the
+    /// next frame on, usually from the [`TimeControls`](crate::time::TimeControls) of the
+    /// clock.
+    pub fn set_time(&mut self, time: Duration, delta: Duration) {
+        self.constants.time = time.as_secs_f32();
+        self.constants.delta_time = delta.as_secs_f32();
+    }
+
+    /// Sets the camera matrices used from the next frame on.
+    pub fn set_camera(&mut self, view: Mat4, projection: Mat4) {
+        self.constants.view = view;
+        self.constants.projection = projection;
+        self.constants.view_projection = projection * view;
+    }
+
+    /// Sets the camera matrices used from the next frame on from a camera, projected for the
+    /// aspect ratio of the swapchain.
+    pub fn update_camera(&mut self, camera: &impl Camera) {
+        self.set_camera(camera.view(), camera.projection(self.aspect_ratio()));
+    }
+
+    /// Returns the width to height ratio of the swapchain.
+    pub fn aspect_ratio(&self) -> f32 {
+        let extent = self.context.data.swapchain_extent;
+        extent.width as f32 / extent.height.max(1) as f32
+    }
+
+    /// Sets the subpixel jitter for temporal anti-aliasing used from the next frame on.
+    pub fn set_jitter(&mut self, jitter: Vec2) {
+        self.constants.jitter = jitter;
+    }
+
+    /// Sets how far the next frame is between the last two simulation ticks, from 0 to 1.
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.constants.alpha = alpha;
+    }
+
+    /// Sets the debug flags passed to shaders from the next frame on.
+    pub fn set_debug_flags(&mut self, flags: u32) {
+        self.constants.debug_flags = flags;
+    }
+
+    /// Marks the swapchain as out of date so it is recreated before the next frame is drawn.
+    pub fn resize(&mut self) {
+        self.swapchain_outdated = true;
+    }
+
+    /// Prepares to render again after frames were not rendered for a while, such as while the
+    /// window was minimized. The time in between is not measured as a frame, and the swapchain
+    /// is recreated in case the window changed.
+    pub fn resume(&mut self) {
+        self.limiter.resume();
+        self.swapchain_outdated = true;
+    }
+
+    /// Returns the preferred present mode.
+    pub fn present_mode(&self) -> PresentModePreference {
+        self.context.data.present_mode_preference
+    }
+
+    /// Sets the preferred present mode, recreating the swapchain before the next frame.
+    pub fn set_present_mode(&mut self, preference: PresentModePreference) {
+        if self.context.data.present_mode_preference != preference {
+            self.context.data.present_mode_preference = preference;
+            self.swapchain_outdated = true;
+        }
+    }
+
+    /// Enables (FIFO) or disables (immediate) VSync, recreating the swapchain before the next
+    /// frame.
+    pub fn set_vsync(&mut self, enabled: bool) {
+        self.set_present_mode(if enabled {
+            PresentModePreference::Fifo
+        } else {
+            PresentModePreference::Immediate
+        });
+    }
+
+    /// Returns the maximum number of frames per second, or `None` when unlimited.
+    pub fn max_fps(&self) -> Option<u32> {
+        self.limiter.max_fps()
+    }
+
+    /// Sets the maximum number of frames per second, or `None` for no limit.
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        self.limiter.set_max_fps(max_fps);
+    }
+
+    /// Returns the target and measured frame times.
+    pub fn frame_pacing(&self) -> FramePacing {
+        self.limiter.pacing()
+    }
+
+    /// Returns the number of samples per pixel currently used for MSAA.
+    pub fn msaa_samples(&self) -> u32 {
+        self.context.data.msaa_samples.bits()
+    }
+
+    /// Sets the number of samples per pixel for MSAA (1 disables it), recreating the render
+    /// targets after the next frame.
+    pub fn set_msaa_samples(&mut self, samples: u32) {
+        if self.context.data.requested_msaa_samples != samples {
+            self.context.data.requested_msaa_samples = samples;
+            self.targets_outdated = true;
+        }
+    }
+
+    /// Returns the render resolution relative to the swapchain extent.
+    pub fn render_scale(&self) -> f32 {
+        self.context.data.render_scale
+    }
+
+    /// Sets the render resolution relative to the swapchain extent (clamped to 0.25 to 4),
+    /// recreating the render targets after the next frame.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let scale = scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        if self.context.data.render_scale != scale {
+            self.context.data.render_scale = scale;
+            self.targets_outdated = true;
+        }
+    }
+
+    /// Returns the resolution frames are rendered at before being scaled to the swapchain.
+    pub fn render_extent(&self) -> vk::Extent2D {
+        self.context.data.render_extent
+    }
+
+    /// Updates the frame constants that change every frame.
+    fn update_constants(&mut self) {
+        let extent = self.context.data.render_extent;
+        self.constants.viewport_size = Vec2::new(extent.width as f32, extent.height as f32);
+    }
+
+    /// Recreates the swapchain after a frame was skipped or presented at a stale size, and
+    /// records the new render settings for crash reports.
+    unsafe fn recreate_swapchain(&mut self, window: &WinitWindow) -> Result<()> {
+        self.context.recreate_swapchain(window)?;
+        crash::record_context(&self.context);
+        self.redraw_needed = true;
+        Ok(())
+    }
+
+    /// Waits for the in-flight fence of a frame, reporting a GPU hang if it does not signal in
+    /// time.
+    unsafe fn wait_for_fence(&self, frame: usize) -> Result<()> {
+        wait_for_fence(
+            &self.context.device,
+            self.context.data.frames.get(frame).in_flight_fence,
+            self.gpu_timeout,
+            &self.checkpoints[frame],
+        )
+    }
+
+    /// Destroys the renderer and its Vulkan context.
+    ///
+    /// # Safety
+    ///
+    /// The renderer must not be used again after it has been destroyed.
+    pub unsafe fn destroy(&mut self) {
+        self.context.destroy();
+    }
+}
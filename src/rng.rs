@@ -0,0 +1,208 @@
+//! A seeded random number generator for reproducible procedural content.
+//!
+//! Every generator takes an [`Rng`] for its own named stream from [`Renderer::rng`], so adding
+//! a generator or drawing more numbers in one does not change the numbers drawn in another.
+//! The seed comes from [`RendererConfig::seed`], or the `VULKANRS_SEED` environment variable
+//! when none is configured.
+//!
+//! [`Renderer::rng`]: crate::Renderer::rng
+//! [`RendererConfig::seed`]: crate::RendererConfig::seed
+
+use std::env;
+
+use glam::Vec3;
+use log::*;
+
+/// The seed used when neither the config nor `VULKANRS_SEED` sets one.
+pub const DEFAULT_SEED: u64 = 0x5EED;
+
+/// Returns the configured seed, or the seed from `VULKANRS_SEED` when none is configured, and
+/// logs the seed used and where it came from.
+pub fn get_seed(configured: Option<u64>) -> u64 {
+    let (seed, source) = if let Some(seed) = configured {
+        (seed, "the renderer config")
+    } else if let Some(seed) = get_env_seed() {
+        (seed, "`VULKANRS_SEED`")
+    } else {
+        (DEFAULT_SEED, "the default")
+    };
+
+    info!("Using random seed {seed} from {source}.");
+    seed
+}
+
+/// Returns the seed from `VULKANRS_SEED`, if set and valid.
+fn get_env_seed() -> Option<u64> {
+    match env::var("VULKANRS_SEED").map(|v| v.trim().parse::<u64>()) {
+        Ok(Ok(seed)) => Some(seed),
+        Ok(Err(error)) => {
+            warn!("Ignoring invalid `VULKANRS_SEED`: {error}");
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// A PCG32 (XSH RR) generator. Small, fast and identical on every platform, but not suitable
+/// for cryptography.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+
+    /// Creates a generator for a seed on the default stream.
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+
+    /// Creates a generator for a seed on one of 2^63 independent streams.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Creates a generator for a seed on the stream identified by a name.
+    pub fn named(seed: u64, name: &str) -> Self {
+        Self::with_stream(seed, fnv1a(name.as_bytes()))
+    }
+
+    /// Returns a uniformly distributed `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(self.increment);
+
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+
+    /// Returns a uniformly distributed `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// Returns a uniformly distributed `f32` in `0..1`.
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fill the mantissa exactly.
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a uniformly distributed `f32` in `min..max`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Returns a uniformly distributed `u32` in `0..bound`, or 0 when `bound` is 0.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        if bound == 0 {
+            return 0;
+        }
+
+        // Reject the values that would make the lower results more likely.
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = self.next_u32();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Returns `true` with a probability from 0 to 1.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// Returns a uniformly distributed direction.
+    pub fn unit_vector(&mut self) -> Vec3 {
+        let z = self.range_f32(-1.0, 1.0);
+        let angle = self.range_f32(0.0, std::f32::consts::TAU);
+        let radius = (1.0 - z * z).sqrt();
+        Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+    }
+
+    /// Shuffles a slice in place.
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            values.swap(i, self.below(i as u32 + 1) as usize);
+        }
+    }
+}
+
+/// Hashes bytes with 64-bit FNV-1a, which is stable across platforms and Rust versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_reference_pcg32() {
+        // The first outputs of the PCG32 reference implementation for seed 42 on stream 54.
+        let mut rng = Rng::with_stream(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+        for value in expected {
+            assert_eq!(rng.next_u32(), value);
+        }
+    }
+
+    #[test]
+    fn same_seed_and_stream_repeat() {
+        let mut a = Rng::named(7, "terrain");
+        let mut b = Rng::named(7, "terrain");
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn streams_and_seeds_differ() {
+        let first = |mut rng: Rng| (0..4).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_ne!(
+            first(Rng::named(7, "terrain")),
+            first(Rng::named(7, "foliage"))
+        );
+        assert_ne!(first(Rng::new(1)), first(Rng::new(2)));
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = Rng::new(DEFAULT_SEED);
+        for _ in 0..1000 {
+            let f = rng.next_f32();
+            assert!((0.0..1.0).contains(&f));
+            assert!((-2.0..3.0).contains(&rng.range_f32(-2.0, 3.0)));
+            assert!(rng.below(10) < 10);
+            assert!((rng.unit_vector().length() - 1.0).abs() < 1e-5);
+        }
+        assert_eq!(rng.below(0), 0);
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut values = (0..50).collect::<Vec<_>>();
+        Rng::new(DEFAULT_SEED).shuffle(&mut values);
+        assert_ne!(values, (0..50).collect::<Vec<_>>());
+        values.sort();
+        assert_eq!(values, (0..50).collect::<Vec<_>>());
+    }
+}
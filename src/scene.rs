@@ -0,0 +1,271 @@
+//! Scenes of meshes, materials, textures and nodes, and their upload to the GPU.
+
+use anyhow::{Result, anyhow};
+use glam::{Mat4, Vec3, Vec4};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::vulkan::{
+    ContextData, IndexBuffer, MeshVertex, SamplerDesc, Texture2D, TextureOptions, TextureSource,
+    VertexBuffer,
+    buffer::{as_bytes, create_device_local_buffers},
+};
+
+/// A scene imported from an asset, such as with [`assets::gltf::load`](crate::assets::gltf::load).
+///
+/// Meshes, materials and textures refer to each other by index.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub textures: Vec<TextureData>,
+    pub nodes: Vec<Node>,
+}
+
+/// A mesh made of one or more primitives, each drawn with its own material.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+}
+
+/// An indexed triangle list with interleaved vertex attributes.
+#[derive(Clone, Debug, Default)]
+pub struct Primitive {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    /// The index of the material, or `None` for the default material.
+    pub material: Option<usize>,
+}
+
+/// How the alpha of a material's base color is interpreted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Alpha is ignored.
+    #[default]
+    Opaque,
+    /// Fragments with alpha below [`Material::alpha_cutoff`] are discarded.
+    Mask,
+    /// Fragments are blended with what is behind them.
+    Blend,
+}
+
+/// A metallic-roughness material.
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: Option<String>,
+    pub base_color_factor: Vec4,
+    pub base_color_texture: Option<usize>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub emissive_factor: Vec3,
+    pub emissive_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color_factor: Vec4::ONE,
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            emissive_factor: Vec3::ZERO,
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+/// The decoded pixels of a texture and how it is sampled.
+#[derive(Clone, Debug, Default)]
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed 8-bit RGBA pixels.
+    pub pixels: Vec<u8>,
+    /// Whether the pixels are sRGB encoded colors, as opposed to linear data such as normals.
+    pub srgb: bool,
+    pub sampler: SamplerDesc,
+}
+
+/// An instance of a mesh in the scene.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: Option<String>,
+    /// Transforms the mesh into world space, including the transforms of every parent node.
+    pub transform: Mat4,
+    /// The index of the mesh, or `None` for nodes that only group other nodes.
+    pub mesh: Option<usize>,
+}
+
+//================================================
+// GPU Scene
+//================================================
+
+/// The range of the shared vertex and index buffers that holds a primitive.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrimitiveRange {
+    pub first_index: u32,
+    pub index_count: u32,
+    pub vertex_offset: i32,
+    pub material: Option<usize>,
+}
+
+/// A primitive to draw with a transform.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Draw {
+    pub range: PrimitiveRange,
+    pub transform: Mat4,
+}
+
+/// A [`Scene`] uploaded to device local memory.
+///
+/// Every primitive shares one vertex buffer and one index buffer.
+#[derive(Clone, Debug, Default)]
+pub struct GpuScene {
+    pub vertex_buffer: VertexBuffer<MeshVertex>,
+    pub index_buffer: IndexBuffer,
+    /// The primitive ranges of each mesh, indexed like [`Scene::meshes`].
+    pub meshes: Vec<Vec<PrimitiveRange>>,
+    /// The textures, indexed like [`Scene::textures`].
+    pub textures: Vec<Texture2D>,
+    /// Every primitive of every node with a mesh.
+    pub draws: Vec<Draw>,
+}
+
+impl Scene {
+    /// Uploads the scene geometry in a single staging pass and creates its textures.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device, and [`GpuScene::destroy`] must be called
+    /// before the device is destroyed.
+    pub unsafe fn upload(
+        &self,
+        instance: &Instance,
+        device: &Device,
+        data: &mut ContextData,
+    ) -> Result<GpuScene> {
+        // Geometry
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+
+        let meshes = self
+            .meshes
+            .iter()
+            .map(|m| {
+                m.primitives
+                    .iter()
+                    .map(|p| {
+                        let range = PrimitiveRange {
+                            first_index: indices.len() as u32,
+                            index_count: p.indices.len() as u32,
+                            vertex_offset: vertices.len() as i32,
+                            material: p.material,
+                        };
+
+                        vertices.extend_from_slice(&p.vertices);
+                        indices.extend_from_slice(&p.indices);
+                        range
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if vertices.is_empty() || indices.is_empty() {
+            return Err(anyhow!("Cannot upload a scene without geometry."));
+        }
+
+        // `MeshVertex` and `u32` contain no padding bytes.
+        let buffers = create_device_local_buffers(
+            instance,
+            device,
+            data,
+            &[
+                (as_bytes(&vertices), vk::BufferUsageFlags::VERTEX_BUFFER),
+                (as_bytes(&indices), vk::BufferUsageFlags::INDEX_BUFFER),
+            ],
+        )?;
+
+        let (vertex_buffer, vertex_buffer_memory) = buffers[0];
+        let mut vertex_buffer =
+            VertexBuffer::from_raw(vertex_buffer, vertex_buffer_memory, vertices.len() as u32);
+
+        let (index_buffer, index_buffer_memory) = buffers[1];
+        let mut index_buffer =
+            IndexBuffer::from_raw::<u32>(index_buffer, index_buffer_memory, indices.len() as u32);
+
+        // Textures
+
+        let sources = self
+            .textures
+            .iter()
+            .map(|t| TextureSource {
+                width: t.width,
+                height: t.height,
+                pixels: &t.pixels,
+                options: TextureOptions {
+                    srgb: t.srgb,
+                    sampler: t.sampler,
+                    ..TextureOptions::default()
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let textures = match Texture2D::from_rgba8_batch(instance, device, data, &sources) {
+            Ok(textures) => textures,
+            Err(error) => {
+                index_buffer.destroy(device);
+                vertex_buffer.destroy(device);
+                return Err(error);
+            }
+        };
+
+        // Draws
+
+        let draws = self
+            .nodes
+            .iter()
+            .filter_map(|n| n.mesh.map(|m| (n.transform, &meshes[m])))
+            .flat_map(|(transform, ranges)| {
+                ranges.iter().map(move |range| Draw {
+                    range: *range,
+                    transform,
+                })
+            })
+            .collect();
+
+        Ok(GpuScene {
+            vertex_buffer,
+            index_buffer,
+            meshes,
+            textures,
+            draws,
+        })
+    }
+}
+
+impl GpuScene {
+    /// Destroys the buffers and textures of the scene.
+    ///
+    /// # Safety
+    ///
+    /// The scene must no longer be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &Device, data: &mut ContextData) {
+        self.textures
+            .iter_mut()
+            .for_each(|t| t.destroy(device, data));
+        self.index_buffer.destroy(device);
+        self.vertex_buffer.destroy(device);
+    }
+}
@@ -0,0 +1,479 @@
+//! Frame timing, fixed timestep simulation and animation time controls.
+
+use std::time::{Duration, Instant};
+
+use log::*;
+use winit::keyboard::KeyCode;
+
+/// The default simulation rate in ticks per second.
+pub const DEFAULT_TICK_RATE: f64 = 60.0;
+
+/// The most ticks run for one frame. Time beyond that is dropped, so a slow frame does not make
+/// every following frame slower by simulating more ticks.
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Splits variable frame times into fixed simulation ticks with an accumulator.
+///
+/// Rendering happens between the last two ticks, so transforms are interpolated between them
+/// with [`FixedTimestep::alpha`].
+#[derive(Copy, Clone, Debug)]
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(DEFAULT_TICK_RATE)
+    }
+}
+
+impl FixedTimestep {
+    /// Creates a timestep that ticks at a rate in ticks per second.
+    pub fn new(tick_rate: f64) -> Self {
+        Self {
+            step: Duration::from_secs_f64(1.0 / tick_rate.max(1.0)),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Returns the duration of one tick.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds the duration of a frame and returns how many ticks to run for it.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            ticks += 1;
+        }
+
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.accumulator = Duration::ZERO;
+            ticks = MAX_TICKS_PER_FRAME;
+        }
+
+        ticks
+    }
+
+    /// Returns how far the current frame is between the previous tick and the next one, from 0
+    /// to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+    }
+}
+
+//================================================
+// Clock
+//================================================
+
+/// Measures frames and drives a fixed timestep update separate from the variable rate render.
+/// The time simulated each frame passes through the [`TimeControls`] first.
+///
+/// ```no_run
+/// # let mut clock = vulkanrs::time::Clock::default();
+/// loop {
+///     clock.advance(|step| {
+///         // Simulate `step` seconds.
+///     });
+///     // Render between the last two ticks with `clock.alpha()`.
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Clock {
+    timestep: FixedTimestep,
+    last_frame: Option<Instant>,
+    delta: Duration,
+    elapsed: Duration,
+    frame_count: u64,
+    tick_count: u64,
+    controls: TimeControls,
+}
+
+impl Clock {
+    /// Creates a clock that ticks at a rate in ticks per second.
+    pub fn new(tick_rate: f64) -> Self {
+        Self {
+            timestep: FixedTimestep::new(tick_rate),
+            ..Self::default()
+        }
+    }
+
+    /// Measures the frame since the previous call, then calls `update` with the tick duration
+    /// once for every tick that is due.
+    pub fn advance(&mut self, mut update: impl FnMut(Duration)) {
+        for _ in 0..self.measure() {
+            update(self.timestep.step());
+        }
+    }
+
+    /// Measures the frame since the previous call and returns the number of ticks that are due,
+    /// each of which simulates [`Clock::step`]. Use [`Clock::advance`] unless the ticks need
+    /// to borrow what owns the clock.
+    pub fn measure(&mut self) -> u32 {
+        let now = Instant::now();
+        self.delta = self.last_frame.map_or(Duration::ZERO, |t| now - t);
+        self.last_frame = Some(now);
+        self.elapsed += self.delta;
+        self.frame_count += 1;
+
+        let scaled = self.controls.advance(self.delta, self.timestep.step());
+        let ticks = self.timestep.advance(scaled);
+        self.tick_count += ticks as u64;
+        ticks
+    }
+
+    /// Forgets the previous frame, so time spent suspended is not simulated by the next frame.
+    pub fn resume(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// Returns the real duration of the current frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the real time elapsed since the first frame.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the duration of one tick.
+    pub fn step(&self) -> Duration {
+        self.timestep.step()
+    }
+
+    /// Returns how far the current frame is between the previous tick and the next one, from 0
+    /// to 1.
+    pub fn alpha(&self) -> f32 {
+        self.timestep.alpha()
+    }
+
+    /// Returns the number of frames measured so far.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Returns the number of ticks run so far.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// Returns the controls that scale, pause and single-step the simulated time.
+    pub fn time_controls(&self) -> &TimeControls {
+        &self.controls
+    }
+
+    /// Returns the controls that scale, pause and single-step the simulated time for
+    /// modification.
+    pub fn time_controls_mut(&mut self) -> &mut TimeControls {
+        &mut self.controls
+    }
+}
+
+//================================================
+// Frame Limiter
+//================================================
+
+/// How long before a frame deadline the limiter stops sleeping and spins, since sleeps can
+/// overshoot by about a scheduler quantum.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// The weight of the newest frame in the average frame time.
+const AVERAGE_WEIGHT: f64 = 0.1;
+
+/// Target and measured frame times of a [`FrameLimiter`].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FramePacing {
+    /// The frame time the limiter aims for, or `None` when it is unlimited.
+    pub target: Option<Duration>,
+    /// The time between the last two frames.
+    pub frame_time: Duration,
+    /// An exponential moving average of the frame time.
+    pub average_frame_time: Duration,
+    /// The time the limiter waited after the last frame.
+    pub wait_time: Duration,
+}
+
+/// Caps the frame rate by waiting between presents, sleeping for most of the wait and spinning
+/// for the rest to hit the deadline precisely.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameLimiter {
+    target: Option<Duration>,
+    deadline: Option<Instant>,
+    last_frame: Option<Instant>,
+    pacing: FramePacing,
+}
+
+impl FrameLimiter {
+    /// Creates a limiter for a maximum number of frames per second, or an unlimited one.
+    pub fn new(max_fps: Option<u32>) -> Self {
+        let mut limiter = Self::default();
+        limiter.set_max_fps(max_fps);
+        limiter
+    }
+
+    /// Sets the maximum number of frames per second, or `None` for no limit.
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        self.target = max_fps
+            .filter(|f| *f > 0)
+            .map(|f| Duration::from_secs_f64(1.0 / f as f64));
+        self.deadline = None;
+        self.pacing.target = self.target;
+    }
+
+    /// Returns the maximum number of frames per second, or `None` when unlimited.
+    pub fn max_fps(&self) -> Option<u32> {
+        self.target.map(|t| (1.0 / t.as_secs_f64()).round() as u32)
+    }
+
+    /// Forgets the previous frame and deadline, so time spent suspended is not measured as a
+    /// frame.
+    pub fn resume(&mut self) {
+        self.deadline = None;
+        self.last_frame = None;
+    }
+
+    /// Returns the target and measured frame times.
+    pub fn pacing(&self) -> FramePacing {
+        self.pacing
+    }
+
+    /// Waits until the next frame is due and measures the frame that just ended.
+    pub fn wait(&mut self) {
+        let start = Instant::now();
+
+        if let Some(target) = self.target {
+            // Deadlines advance by the target so that short sleeps and long sleeps even out,
+            // but restart after a slow frame instead of rushing to catch up.
+            let deadline = self.deadline.map_or(start + target, |d| d.max(start));
+
+            if let Some(sleep) = deadline.checked_duration_since(start + SPIN_MARGIN) {
+                std::thread::sleep(sleep);
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+
+            self.deadline = Some(deadline + target);
+        }
+
+        let now = Instant::now();
+        self.pacing.wait_time = now - start;
+
+        if let Some(last_frame) = self.last_frame {
+            let frame_time = now - last_frame;
+            self.pacing.frame_time = frame_time;
+            self.pacing.average_frame_time = if self.pacing.average_frame_time.is_zero() {
+                frame_time
+            } else {
+                self.pacing.average_frame_time.mul_f64(1.0 - AVERAGE_WEIGHT)
+                    + frame_time.mul_f64(AVERAGE_WEIGHT)
+            };
+        }
+
+        self.last_frame = Some(now);
+    }
+}
+
+//================================================
+// Time Controls
+//================================================
+
+/// Scales, pauses and single-steps the time a [`Clock`] simulates, for debugging animated
+/// content.
+///
+/// Simulation ticks and animation time both follow the scaled time, so pausing freezes
+/// animation, particles and everything else updated in ticks. The camera keeps moving in real
+/// time so a paused scene can still be inspected. The hotkeys are enabled by default and can be
+/// turned off with [`TimeControls::set_hotkeys_enabled`].
+///
+/// | Key | Action                          |
+/// |-----|---------------------------------|
+/// | `P` | Pause or resume                 |
+/// | `.` | Pause and advance a single tick |
+/// | `[` | Halve the time scale            |
+/// | `]` | Double the time scale           |
+/// | `\` | Reset the time scale to 1       |
+#[derive(Copy, Clone, Debug)]
+pub struct TimeControls {
+    scale: f32,
+    paused: bool,
+    step_requested: bool,
+    hotkeys: bool,
+    elapsed: Duration,
+    delta: Duration,
+}
+
+impl Default for TimeControls {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            paused: false,
+            step_requested: false,
+            hotkeys: true,
+            elapsed: Duration::ZERO,
+            delta: Duration::ZERO,
+        }
+    }
+}
+
+impl TimeControls {
+    /// Advances by a real duration and returns the scaled duration, which is exactly one tick
+    /// of `step` after a single step was requested while paused.
+    pub fn advance(&mut self, real: Duration, step: Duration) -> Duration {
+        self.delta = if !self.paused {
+            real.mul_f32(self.scale)
+        } else if std::mem::take(&mut self.step_requested) {
+            step
+        } else {
+            Duration::ZERO
+        };
+
+        self.elapsed += self.delta;
+        self.delta
+    }
+
+    /// Applies the hotkey of a pressed key, unless the hotkeys are disabled.
+    pub fn handle_key(&mut self, key: KeyCode) {
+        if !self.hotkeys {
+            return;
+        }
+
+        match key {
+            KeyCode::KeyP => self.set_paused(!self.paused),
+            KeyCode::Period => self.step(),
+            KeyCode::BracketLeft => self.set_scale(self.scale / 2.0),
+            KeyCode::BracketRight => self.set_scale(self.scale * 2.0),
+            KeyCode::Backslash => self.set_scale(1.0),
+            _ => {}
+        }
+    }
+
+    /// Returns whether the hotkeys are enabled.
+    pub fn hotkeys_enabled(&self) -> bool {
+        self.hotkeys
+    }
+
+    /// Enables or disables the hotkeys, such as when the keys are needed for something else.
+    pub fn set_hotkeys_enabled(&mut self, enabled: bool) {
+        self.hotkeys = enabled;
+    }
+
+    /// Returns the scaled time elapsed so far.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the scaled duration of the current frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the factor real time is multiplied by.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Sets the factor real time is multiplied by, where values below 1 are slow motion.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(1.0 / 64.0, 64.0);
+        info!("Time scale set to {}.", self.scale);
+    }
+
+    /// Returns whether time is paused.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pauses or resumes time.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.step_requested = false;
+        info!("Time {}.", if paused { "paused" } else { "resumed" });
+    }
+
+    /// Pauses time and advances it by one tick on the next frame.
+    pub fn step(&mut self) {
+        if !self.paused {
+            self.set_paused(true);
+        }
+        self.step_requested = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn timestep_ticks_and_alpha() {
+        let mut timestep = FixedTimestep::new(10.0);
+        assert_eq!(timestep.step(), STEP);
+        assert_eq!(timestep.advance(Duration::from_millis(250)), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(timestep.advance(Duration::from_millis(50)), 1);
+        assert!(timestep.alpha().abs() < 1e-4);
+    }
+
+    #[test]
+    fn timestep_caps_ticks_per_frame() {
+        let mut timestep = FixedTimestep::new(10.0);
+        assert_eq!(
+            timestep.advance(Duration::from_secs(10)),
+            MAX_TICKS_PER_FRAME
+        );
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn controls_scale() {
+        let mut controls = TimeControls::default();
+        controls.set_scale(2.0);
+        assert_eq!(controls.advance(STEP, STEP), STEP * 2);
+        controls.set_scale(1000.0);
+        assert_eq!(controls.scale(), 64.0);
+        controls.set_scale(0.0);
+        assert_eq!(controls.scale(), 1.0 / 64.0);
+    }
+
+    #[test]
+    fn controls_pause_and_step() {
+        let mut controls = TimeControls::default();
+        controls.set_paused(true);
+        assert_eq!(controls.advance(STEP, STEP), Duration::ZERO);
+
+        controls.step();
+        assert_eq!(controls.advance(Duration::from_millis(3), STEP), STEP);
+        assert_eq!(
+            controls.advance(Duration::from_millis(3), STEP),
+            Duration::ZERO
+        );
+        assert_eq!(controls.elapsed(), STEP);
+
+        controls.set_paused(false);
+        assert_eq!(controls.advance(STEP, STEP), STEP);
+    }
+
+    #[test]
+    fn controls_hotkeys() {
+        let mut controls = TimeControls::default();
+        controls.handle_key(KeyCode::KeyP);
+        assert!(controls.paused());
+        controls.handle_key(KeyCode::BracketRight);
+        assert_eq!(controls.scale(), 2.0);
+
+        controls.set_hotkeys_enabled(false);
+        controls.handle_key(KeyCode::KeyP);
+        controls.handle_key(KeyCode::Backslash);
+        assert!(controls.paused());
+        assert_eq!(controls.scale(), 2.0);
+    }
+}
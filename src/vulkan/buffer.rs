@@ -0,0 +1,407 @@
+//! Buffer creation and the typed vertex and index buffers built on top of it.
+
+use std::{marker::PhantomData, ptr::copy_nonoverlapping as memcpy};
+
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_0::*;
+
+use super::{
+    ContextData,
+    command::{begin_single_time_commands, end_single_time_commands},
+    memory::get_memory_type_index,
+    vertex::Vertex,
+};
+
+/// Creates a buffer and binds newly allocated memory with the given properties to it.
+pub(crate) unsafe fn create_buffer(
+    instance: &Instance,
+    device: &Device,
+    data: &ContextData,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    // Buffer
+
+    let buffer_info = vk::BufferCreateInfo::builder()
+        .size(size)
+        .usage(usage)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+    let buffer = device.create_buffer(&buffer_info, None)?;
+
+    // Memory
+
+    let requirements = device.get_buffer_memory_requirements(buffer);
+
+    let memory_info = vk::MemoryAllocateInfo::builder()
+        .allocation_size(requirements.size)
+        .memory_type_index(get_memory_type_index(
+            instance,
+            data,
+            properties,
+            requirements,
+        )?);
+
+    let buffer_memory = device.allocate_memory(&memory_info, None)?;
+
+    device.bind_buffer_memory(buffer, buffer_memory, 0)?;
+
+    Ok((buffer, buffer_memory))
+}
+
+/// Creates a device local buffer and fills it with `bytes` through a staging buffer.
+pub(crate) unsafe fn create_device_local_buffer(
+    instance: &Instance,
+    device: &Device,
+    data: &ContextData,
+    bytes: &[u8],
+    usage: vk::BufferUsageFlags,
+) -> Result<(vk::Buffer, vk::DeviceMemory)> {
+    Ok(create_device_local_buffers(instance, device, data, &[(bytes, usage)])?[0])
+}
+
+/// Creates a device local buffer for each of `contents` and fills them all through a single
+/// staging buffer and a single submission.
+pub(crate) unsafe fn create_device_local_buffers(
+    instance: &Instance,
+    device: &Device,
+    data: &ContextData,
+    contents: &[(&[u8], vk::BufferUsageFlags)],
+) -> Result<Vec<(vk::Buffer, vk::DeviceMemory)>> {
+    let size = contents.iter().map(|(b, _)| b.len() as u64).sum::<u64>();
+
+    // Staging Buffer
+
+    let (staging_buffer, staging_buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+
+    let mut offset = 0;
+    for (bytes, _) in contents {
+        memcpy(bytes.as_ptr(), memory.cast::<u8>().add(offset), bytes.len());
+        offset += bytes.len();
+    }
+
+    device.unmap_memory(staging_buffer_memory);
+
+    // Buffers
+
+    let buffers = contents
+        .iter()
+        .map(|(bytes, usage)| {
+            create_buffer(
+                instance,
+                device,
+                data,
+                bytes.len() as u64,
+                vk::BufferUsageFlags::TRANSFER_DST | *usage,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Copy
+
+    let command_buffer = begin_single_time_commands(device, data)?;
+
+    let mut offset = 0;
+    for ((bytes, _), (buffer, _)) in contents.iter().zip(&buffers) {
+        let regions = vk::BufferCopy::builder()
+            .src_offset(offset)
+            .size(bytes.len() as u64);
+        device.cmd_copy_buffer(command_buffer, staging_buffer, *buffer, &[regions]);
+        offset += bytes.len() as u64;
+    }
+
+    end_single_time_commands(device, data, command_buffer)?;
+
+    device.destroy_buffer(staging_buffer, None);
+    device.free_memory(staging_buffer_memory, None);
+
+    Ok(buffers)
+}
+
+/// Returns the bytes of a slice of plain data.
+///
+/// # Safety
+///
+/// `T` must be a `#[repr(C)]` type without padding bytes, since padding is uninitialized and
+/// reading it as `u8` is undefined behavior.
+pub(crate) unsafe fn as_bytes<T: Copy>(values: &[T]) -> &[u8] {
+    std::slice::from_raw_parts(values.as_ptr().cast(), size_of_val(values))
+}
+
+//================================================
+// Vertex Buffer
+//================================================
+
+/// A device local buffer of vertices.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct VertexBuffer<V: Vertex> {
+    pub buffer: vk::Buffer,
+    pub memory: vk::DeviceMemory,
+    len: u32,
+    _vertex: PhantomData<V>,
+}
+
+impl<V: Vertex> VertexBuffer<V> {
+    /// Uploads vertices into a new vertex buffer.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device, and [`VertexBuffer::destroy`] must be called
+    /// before the device is destroyed. `V` must be a `#[repr(C)]` struct without padding bytes,
+    /// since the vertices are uploaded as raw bytes.
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        data: &ContextData,
+        vertices: &[V],
+    ) -> Result<Self> {
+        if vertices.is_empty() {
+            return Err(anyhow!("Cannot create an empty vertex buffer."));
+        }
+
+        let (buffer, memory) = create_device_local_buffer(
+            instance,
+            device,
+            data,
+            as_bytes(vertices),
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+        )?;
+
+        Ok(Self::from_raw(buffer, memory, vertices.len() as u32))
+    }
+
+    /// Wraps a device local buffer holding `len` vertices.
+    pub(crate) fn from_raw(buffer: vk::Buffer, memory: vk::DeviceMemory, len: u32) -> Self {
+        Self {
+            buffer,
+            memory,
+            len,
+            _vertex: PhantomData,
+        }
+    }
+
+    /// Returns the number of vertices.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns whether the buffer holds no vertices, which is only the case before creation.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Binds the buffer to a vertex input binding.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must be recording.
+    pub unsafe fn bind(&self, device: &Device, command_buffer: vk::CommandBuffer, binding: u32) {
+        device.cmd_bind_vertex_buffers(command_buffer, binding, &[self.buffer], &[0]);
+    }
+
+    /// Destroys the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must no longer be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        device.destroy_buffer(self.buffer, None);
+        device.free_memory(self.memory, None);
+    }
+}
+
+//================================================
+// Index Buffer
+//================================================
+
+/// An integer type that can be stored in an [`IndexBuffer`].
+pub trait Index: Copy + 'static {
+    const INDEX_TYPE: vk::IndexType;
+}
+
+impl Index for u16 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT16;
+}
+
+impl Index for u32 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT32;
+}
+
+/// A device local buffer of 16-bit or 32-bit indices.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct IndexBuffer {
+    pub buffer: vk::Buffer,
+    pub memory: vk::DeviceMemory,
+    pub index_type: vk::IndexType,
+    len: u32,
+}
+
+impl IndexBuffer {
+    /// Uploads indices into a new index buffer.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device, and [`IndexBuffer::destroy`] must be called
+    /// before the device is destroyed. `I` must not contain padding bytes, which holds for `u16`
+    /// and `u32`.
+    pub unsafe fn create<I: Index>(
+        instance: &Instance,
+        device: &Device,
+        data: &ContextData,
+        indices: &[I],
+    ) -> Result<Self> {
+        if indices.is_empty() {
+            return Err(anyhow!("Cannot create an empty index buffer."));
+        }
+
+        let (buffer, memory) = create_device_local_buffer(
+            instance,
+            device,
+            data,
+            as_bytes(indices),
+            vk::BufferUsageFlags::INDEX_BUFFER,
+        )?;
+
+        Ok(Self::from_raw::<I>(buffer, memory, indices.len() as u32))
+    }
+
+    /// Wraps a device local buffer holding `len` indices of type `I`.
+    pub(crate) fn from_raw<I: Index>(
+        buffer: vk::Buffer,
+        memory: vk::DeviceMemory,
+        len: u32,
+    ) -> Self {
+        Self {
+            buffer,
+            memory,
+            index_type: I::INDEX_TYPE,
+            len,
+        }
+    }
+
+    /// Returns the number of indices.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns whether the buffer holds no indices, which is only the case before creation.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Binds the buffer for indexed draws.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must be recording.
+    pub unsafe fn bind(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        device.cmd_bind_index_buffer(command_buffer, self.buffer, 0, self.index_type);
+    }
+
+    /// Destroys the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must no longer be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        device.destroy_buffer(self.buffer, None);
+        device.free_memory(self.memory, None);
+    }
+}
+
+//================================================
+// Uniform Buffer
+//================================================
+
+/// A host visible uniform buffer holding a `T` for each frame in flight, so a frame can be
+/// updated while earlier frames are still being rendered.
+#[derive(Clone, Debug, Default)]
+pub struct UniformBuffer<T: Copy> {
+    buffers: Vec<(vk::Buffer, vk::DeviceMemory)>,
+    _value: PhantomData<T>,
+}
+
+impl<T: Copy> UniformBuffer<T> {
+    /// The size of the value in each buffer.
+    pub const SIZE: vk::DeviceSize = size_of::<T>() as vk::DeviceSize;
+
+    /// Creates one uniform buffer for each of `frames` frames in flight.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device, and [`UniformBuffer::destroy`] must be called
+    /// before the device is destroyed.
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        data: &ContextData,
+        frames: usize,
+    ) -> Result<Self> {
+        let buffers = (0..frames)
+            .map(|_| {
+                create_buffer(
+                    instance,
+                    device,
+                    data,
+                    Self::SIZE,
+                    vk::BufferUsageFlags::UNIFORM_BUFFER,
+                    vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            buffers,
+            _value: PhantomData,
+        })
+    }
+
+    /// Returns the buffer of a frame.
+    pub fn buffer(&self, frame: usize) -> vk::Buffer {
+        self.buffers[frame].0
+    }
+
+    /// Returns the number of frames the buffer has a copy for.
+    pub fn frames(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Writes the value of a frame.
+    ///
+    /// # Safety
+    ///
+    /// The frame must not be in use by the GPU.
+    pub unsafe fn update(&self, device: &Device, frame: usize, value: &T) -> Result<()> {
+        let memory = self.buffers[frame].1;
+        let mapped = device.map_memory(memory, 0, Self::SIZE, vk::MemoryMapFlags::empty())?;
+
+        memcpy(value, mapped.cast(), 1);
+
+        device.unmap_memory(memory);
+
+        Ok(())
+    }
+
+    /// Destroys the buffer of every frame.
+    ///
+    /// # Safety
+    ///
+    /// The buffers must no longer be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for (buffer, memory) in self.buffers.drain(..) {
+            device.destroy_buffer(buffer, None);
+            device.free_memory(memory, None);
+        }
+    }
+}
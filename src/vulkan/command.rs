@@ -0,0 +1,153 @@
+//! Command pool creation and command buffer recording.
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{
+    ContextData,
+    descriptor::FRAME_SET,
+    device::QueueFamilyIndices,
+    push::{ObjectConstants, cmd_push_constants},
+    target::cmd_blit_to_swapchain,
+};
+
+pub(crate) unsafe fn create_command_pool(
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+) -> Result<()> {
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+
+    let info = vk::CommandPoolCreateInfo::builder()
+        .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+        .queue_family_index(indices.graphics);
+
+    data.command_pool = device.create_command_pool(&info, None)?;
+
+    Ok(())
+}
+
+/// Records the commands that draw a frame into the render target and blit it to a swapchain
+/// image.
+pub(crate) unsafe fn record_command_buffer(
+    device: &Device,
+    data: &ContextData,
+    frame_index: usize,
+    image_index: usize,
+) -> Result<()> {
+    let command_buffer = data.frames.get(frame_index).command_buffer;
+
+    device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())?;
+
+    let info =
+        vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    device.begin_command_buffer(command_buffer, &info)?;
+
+    let render_area = vk::Rect2D::builder()
+        .offset(vk::Offset2D::default())
+        .extent(data.render_extent);
+
+    let color_clear_value = vk::ClearValue {
+        color: vk::ClearColorValue {
+            float32: [0.0, 0.0, 0.0, 1.0],
+        },
+    };
+
+    let depth_clear_value = vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+            depth: 1.0,
+            stencil: 0,
+        },
+    };
+
+    let clear_values = &[color_clear_value, depth_clear_value];
+    let info = vk::RenderPassBeginInfo::builder()
+        .render_pass(data.render_pass)
+        .framebuffer(data.framebuffer)
+        .render_area(render_area)
+        .clear_values(clear_values);
+
+    device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+    device.cmd_bind_pipeline(
+        command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        data.pipeline,
+    );
+
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(data.render_extent.width as f32)
+        .height(data.render_extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+    device.cmd_set_scissor(command_buffer, 0, &[render_area]);
+    device.cmd_bind_descriptor_sets(
+        command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        data.pipeline_layout,
+        FRAME_SET,
+        &[data.frames.get(frame_index).descriptor_set],
+        &[],
+    );
+    // The triangle is drawn at the origin of world space.
+    cmd_push_constants(
+        device,
+        command_buffer,
+        data.pipeline_layout,
+        &ObjectConstants::default(),
+    );
+    data.vertex_buffer.bind(device, command_buffer, 0);
+    data.index_buffer.bind(device, command_buffer);
+    device.cmd_draw_indexed(command_buffer, data.index_buffer.len(), 1, 0, 0, 0);
+    device.cmd_end_render_pass(command_buffer);
+
+    cmd_blit_to_swapchain(device, data, command_buffer, image_index);
+
+    device.end_command_buffer(command_buffer)?;
+
+    Ok(())
+}
+
+/// Allocates and begins a command buffer for commands that are submitted once, such as uploads.
+pub(crate) unsafe fn begin_single_time_commands(
+    device: &Device,
+    data: &ContextData,
+) -> Result<vk::CommandBuffer> {
+    let info = vk::CommandBufferAllocateInfo::builder()
+        .level(vk::CommandBufferLevel::PRIMARY)
+        .command_pool(data.command_pool)
+        .command_buffer_count(1);
+
+    let command_buffer = device.allocate_command_buffers(&info)?[0];
+
+    let info =
+        vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    device.begin_command_buffer(command_buffer, &info)?;
+
+    Ok(command_buffer)
+}
+
+/// Ends and submits a command buffer from [`begin_single_time_commands`], waits for it to
+/// finish and frees it.
+pub(crate) unsafe fn end_single_time_commands(
+    device: &Device,
+    data: &ContextData,
+    command_buffer: vk::CommandBuffer,
+) -> Result<()> {
+    device.end_command_buffer(command_buffer)?;
+
+    let command_buffers = &[command_buffer];
+    let info = vk::SubmitInfo::builder().command_buffers(command_buffers);
+
+    device.queue_submit(data.graphics_queue, &[info], vk::Fence::null())?;
+    device.queue_wait_idle(data.graphics_queue)?;
+
+    device.free_command_buffers(data.command_pool, command_buffers);
+
+    Ok(())
+}
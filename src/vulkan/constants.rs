@@ -0,0 +1,52 @@
+//! The frame constants uniform block shared by every shader.
+
+use glam::{Mat4, Vec2};
+
+/// The values every shader can rely on, uploaded once per frame to a uniform buffer bound at
+/// [`FRAME_SET`](super::descriptor::FRAME_SET).
+///
+/// The layout matches the std140 `FrameConstants` block in `shaders/include/frame.glsl`, so the
+/// two must be changed together.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameConstants {
+    /// Transforms world space into view space.
+    pub view: Mat4,
+    /// Transforms view space into clip space.
+    pub projection: Mat4,
+    /// `projection * view`.
+    pub view_projection: Mat4,
+    /// The size of the render target in pixels.
+    pub viewport_size: Vec2,
+    /// The subpixel offset applied to the projection for temporal anti-aliasing, in pixels.
+    pub jitter: Vec2,
+    /// The seconds of animation time, which follows the
+    /// [`TimeControls`](crate::time::TimeControls) set with
+    /// [`Renderer::set_time`](crate::Renderer::set_time).
+    pub time: f32,
+    /// The seconds of animation time since the previous frame.
+    pub delta_time: f32,
+    /// The number of frames rendered before this one.
+    pub frame_index: u32,
+    /// Application defined flags for toggling debug visualizations in shaders.
+    pub debug_flags: u32,
+    /// How far the frame is between the last two simulation ticks, from 0 to 1.
+    pub alpha: f32,
+}
+
+impl Default for FrameConstants {
+    fn default() -> Self {
+        Self {
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            view_projection: Mat4::IDENTITY,
+            viewport_size: Vec2::ZERO,
+            jitter: Vec2::ZERO,
+            time: 0.0,
+            delta_time: 0.0,
+            frame_index: 0,
+            debug_flags: 0,
+            alpha: 0.0,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Validation layers and the debug messenger that routes their messages through our logging.
+
+use std::{env, ffi::CStr, os::raw::c_void};
+
+use anyhow::Result;
+use log::*;
+use vulkanalia::{prelude::v1_0::*, vk::ExtDebugUtilsExtension};
+
+use super::ContextData;
+
+/// Whether the validation layers are enabled when not overridden by `VULKANRS_VALIDATION`.
+pub const VALIDATION_ENABLED: bool = cfg!(debug_assertions);
+
+/// The name of the validation layers.
+pub const VALIDATION_LAYER: vk::ExtensionName =
+    vk::ExtensionName::from_bytes(b"VK_LAYER_KHRONOS_validation");
+
+/// Controls the validation layers and which of their messages are logged.
+#[derive(Copy, Clone, Debug)]
+pub struct DebugConfig {
+    /// Whether the validation layers and debug messenger are enabled.
+    pub enabled: bool,
+    /// The least severe messages that are logged.
+    pub min_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    /// The kinds of messages that are logged.
+    pub message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            enabled: VALIDATION_ENABLED,
+            min_severity: vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            message_types: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+        }
+    }
+}
+
+impl DebugConfig {
+    /// Creates a config from the defaults, overridden by these environment variables:
+    ///
+    /// - `VULKANRS_VALIDATION`: `1`/`true`/`on` or `0`/`false`/`off`
+    /// - `VULKANRS_VALIDATION_SEVERITY`: `verbose`, `info`, `warning` or `error`
+    /// - `VULKANRS_VALIDATION_TYPES`: a comma separated list of `general`, `validation` and
+    ///   `performance`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+
+        if let Ok(value) = env::var("VULKANRS_VALIDATION") {
+            match value.to_lowercase().as_str() {
+                "1" | "true" | "on" => config.enabled = true,
+                "0" | "false" | "off" => config.enabled = false,
+                _ => warn!("Ignoring invalid `VULKANRS_VALIDATION`: `{value}`"),
+            }
+        }
+
+        if let Ok(value) = env::var("VULKANRS_VALIDATION_SEVERITY") {
+            match parse_severity(&value) {
+                Some(severity) => config.min_severity = severity,
+                None => warn!("Ignoring invalid `VULKANRS_VALIDATION_SEVERITY`: `{value}`"),
+            }
+        }
+
+        if let Ok(value) = env::var("VULKANRS_VALIDATION_TYPES") {
+            match parse_message_types(&value) {
+                Some(types) => config.message_types = types,
+                None => warn!("Ignoring invalid `VULKANRS_VALIDATION_TYPES`: `{value}`"),
+            }
+        }
+
+        config
+    }
+
+    /// Returns every severity at least as severe as the minimum severity.
+    fn severities(&self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        [
+            vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+        ]
+        .into_iter()
+        .filter(|s| *s >= self.min_severity)
+        .fold(vk::DebugUtilsMessageSeverityFlagsEXT::empty(), |a, s| a | s)
+    }
+}
+
+fn parse_severity(value: &str) -> Option<vk::DebugUtilsMessageSeverityFlagsEXT> {
+    match value.trim().to_lowercase().as_str() {
+        "verbose" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE),
+        "info" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::INFO),
+        "warning" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING),
+        "error" => Some(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR),
+        _ => None,
+    }
+}
+
+fn parse_message_types(value: &str) -> Option<vk::DebugUtilsMessageTypeFlagsEXT> {
+    value
+        .split(',')
+        .map(|t| match t.trim().to_lowercase().as_str() {
+            "general" => Some(vk::DebugUtilsMessageTypeFlagsEXT::GENERAL),
+            "validation" => Some(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION),
+            "performance" => Some(vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE),
+            _ => None,
+        })
+        .try_fold(vk::DebugUtilsMessageTypeFlagsEXT::empty(), |a, t| {
+            Some(a | t?)
+        })
+}
+
+/// Returns the create info for a debug messenger matching a config.
+pub(crate) fn get_debug_messenger_info(
+    config: &DebugConfig,
+) -> vk::DebugUtilsMessengerCreateInfoEXTBuilder<'static> {
+    vk::DebugUtilsMessengerCreateInfoEXT::builder()
+        .message_severity(config.severities())
+        .message_type(config.message_types)
+        .user_callback(Some(debug_callback))
+}
+
+pub(crate) unsafe fn create_debug_messenger(
+    instance: &Instance,
+    data: &mut ContextData,
+) -> Result<()> {
+    let info = get_debug_messenger_info(&data.debug);
+    data.messenger = instance.create_debug_utils_messenger_ext(&info, None)?;
+    Ok(())
+}
+
+extern "system" fn debug_callback(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    type_: vk::DebugUtilsMessageTypeFlagsEXT,
+    data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    _: *mut c_void,
+) -> vk::Bool32 {
+    let data = unsafe { *data };
+    let message = unsafe { CStr::from_ptr(data.message) }.to_string_lossy();
+
+    if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR {
+        error!("({type_:?}) {message}");
+    } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING {
+        warn!("({type_:?}) {message}");
+    } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::INFO {
+        debug!("({type_:?}) {message}");
+    } else {
+        trace!("({type_:?}) {message}");
+    }
+
+    vk::FALSE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_severities() {
+        assert_eq!(
+            parse_severity(" Warning "),
+            Some(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING)
+        );
+        assert_eq!(
+            parse_severity("error"),
+            Some(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+        );
+        assert_eq!(parse_severity("loud"), None);
+    }
+
+    #[test]
+    fn parse_types() {
+        assert_eq!(
+            parse_message_types("general, PERFORMANCE"),
+            Some(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
+            )
+        );
+        assert_eq!(parse_message_types("validation,bogus"), None);
+        assert_eq!(parse_message_types(""), None);
+    }
+
+    #[test]
+    fn severities_from_minimum() {
+        let config = DebugConfig {
+            min_severity: vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            ..DebugConfig::default()
+        };
+        assert_eq!(
+            config.severities(),
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+        );
+    }
+}
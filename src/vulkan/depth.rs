@@ -0,0 +1,92 @@
+//! The depth attachment shared by every framebuffer.
+
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_0::*;
+
+use super::{
+    ContextData,
+    image::{create_image, create_image_view},
+};
+
+/// The depth formats to use, in order of preference.
+const DEPTH_FORMATS: &[vk::Format] = &[
+    vk::Format::D32_SFLOAT,
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::D24_UNORM_S8_UINT,
+];
+
+pub(crate) unsafe fn create_depth_objects(
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+) -> Result<()> {
+    // Image + Image Memory
+
+    let format = get_depth_format(instance, data)?;
+
+    let (depth_image, depth_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.render_extent.width,
+        data.render_extent.height,
+        1,
+        data.msaa_samples,
+        format,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.depth_image = depth_image;
+    data.depth_image_memory = depth_image_memory;
+
+    // Image View
+
+    data.depth_image_view = create_image_view(
+        device,
+        data.depth_image,
+        format,
+        vk::ImageAspectFlags::DEPTH,
+        1,
+    )?;
+
+    Ok(())
+}
+
+/// Returns the first depth format that can be used as an optimally tiled depth attachment.
+pub(crate) unsafe fn get_depth_format(
+    instance: &Instance,
+    data: &ContextData,
+) -> Result<vk::Format> {
+    get_supported_format(
+        instance,
+        data,
+        DEPTH_FORMATS,
+        vk::ImageTiling::OPTIMAL,
+        vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+    )
+}
+
+/// Returns the first candidate format that supports the features with the tiling.
+pub(crate) unsafe fn get_supported_format(
+    instance: &Instance,
+    data: &ContextData,
+    candidates: &[vk::Format],
+    tiling: vk::ImageTiling,
+    features: vk::FormatFeatureFlags,
+) -> Result<vk::Format> {
+    candidates
+        .iter()
+        .cloned()
+        .find(|f| {
+            let properties =
+                instance.get_physical_device_format_properties(data.physical_device, *f);
+            match tiling {
+                vk::ImageTiling::LINEAR => properties.linear_tiling_features.contains(features),
+                vk::ImageTiling::OPTIMAL => properties.optimal_tiling_features.contains(features),
+                _ => false,
+            }
+        })
+        .ok_or_else(|| anyhow!("Failed to find supported format!"))
+}
@@ -0,0 +1,660 @@
+//! Descriptor set slots, layouts and allocation.
+//!
+//! Every shader follows the same set index convention:
+//!
+//! | Set                  | Contents                                   | Bound          |
+//! |----------------------|--------------------------------------------|----------------|
+//! | [`FRAME_SET`] (0)    | [`FrameConstants`](super::FrameConstants)  | once per frame |
+//! | [`PASS_SET`] (1)     | render pass inputs (shadow maps, G-buffer) | once per pass  |
+//! | [`MATERIAL_SET`] (2) | material textures and parameters           | per material   |
+//! | [`OBJECT_SET`] (3)   | per-object data                            | per draw       |
+//!
+//! Pipeline layouts are generated by reflecting the descriptor bindings of a pipeline's shaders,
+//! so the Rust side never has to repeat them. Layouts are cached in a [`LayoutCache`], so
+//! pipelines with the same bindings share the same set and pipeline layouts. Sets themselves are
+//! allocated from a [`DescriptorAllocator`], usually through a [`DescriptorSetBuilder`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Result, anyhow};
+use rspirv_reflect::{BindingCount, Reflection};
+use vulkanalia::prelude::v1_0::*;
+
+use super::ContextData;
+
+/// The set that holds the [`FrameConstants`](super::FrameConstants) of a frame.
+pub const FRAME_SET: u32 = 0;
+
+/// The set that holds the inputs of a render pass.
+pub const PASS_SET: u32 = 1;
+
+/// The set that holds the textures and parameters of a material, such as a
+/// [`Texture2D`](super::Texture2D).
+pub const MATERIAL_SET: u32 = 2;
+
+/// The set that holds the data of a single object.
+pub const OBJECT_SET: u32 = 3;
+
+/// The number of descriptor sets in the convention. Shaders may not use higher set indices.
+pub const SET_COUNT: u32 = 4;
+
+/// A binding in a descriptor set layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DescriptorBinding {
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32,
+    pub stages: vk::ShaderStageFlags,
+}
+
+/// The bindings of the frame constants set, a single uniform buffer visible to every graphics
+/// stage.
+pub const FRAME_BINDINGS: &[DescriptorBinding] = &[DescriptorBinding {
+    binding: 0,
+    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+    count: 1,
+    stages: vk::ShaderStageFlags::ALL_GRAPHICS,
+}];
+
+//================================================
+// Reflection
+//================================================
+
+/// Reflects the descriptor bindings used by a set of shader stages, merging the stages of
+/// bindings that are used by more than one shader.
+pub fn reflect_descriptor_sets(
+    shaders: &[(&[u8], vk::ShaderStageFlags)],
+) -> Result<BTreeMap<u32, Vec<DescriptorBinding>>> {
+    let mut sets = BTreeMap::<u32, BTreeMap<u32, DescriptorBinding>>::new();
+
+    for (bytecode, stage) in shaders {
+        let reflection = Reflection::new_from_spirv(bytecode)?;
+        for (set, bindings) in reflection.get_descriptor_sets()? {
+            if set >= SET_COUNT {
+                return Err(anyhow!(
+                    "Shader ({stage:?}) uses descriptor set {set}, but only sets 0 to {} exist.",
+                    SET_COUNT - 1
+                ));
+            }
+
+            for (binding, info) in bindings {
+                let count = match info.binding_count {
+                    BindingCount::One => 1,
+                    BindingCount::StaticSized(n) => n as u32,
+                    BindingCount::Unbounded => {
+                        return Err(anyhow!(
+                            "Shader ({stage:?}) binding `{}` (set {set}, binding {binding}) is \
+                             unbounded, which is not supported.",
+                            info.name
+                        ));
+                    }
+                };
+
+                let reflected = DescriptorBinding {
+                    binding,
+                    descriptor_type: vk::DescriptorType::from_raw(info.ty.0 as i32),
+                    count,
+                    stages: *stage,
+                };
+
+                let merged = sets
+                    .entry(set)
+                    .or_default()
+                    .entry(binding)
+                    .or_insert(reflected);
+                if merged.descriptor_type != reflected.descriptor_type || merged.count != count {
+                    return Err(anyhow!(
+                        "Shader stages disagree on set {set}, binding {binding}: {:?} vs {:?}.",
+                        merged,
+                        reflected
+                    ));
+                }
+
+                merged.stages |= *stage;
+            }
+        }
+    }
+
+    Ok(sets
+        .into_iter()
+        .map(|(set, bindings)| (set, bindings.into_values().collect()))
+        .collect())
+}
+
+/// Reflects the push constant block used by a set of shader stages, merged into a single range
+/// read by every stage that declares it.
+pub fn reflect_push_constant_range(
+    shaders: &[(&[u8], vk::ShaderStageFlags)],
+) -> Result<Option<vk::PushConstantRange>> {
+    let mut merged = None::<vk::PushConstantRange>;
+
+    for (bytecode, stage) in shaders {
+        let reflection = Reflection::new_from_spirv(bytecode)?;
+        if let Some(info) = reflection.get_push_constant_range()? {
+            let range = merged.get_or_insert_with(|| vk::PushConstantRange {
+                stage_flags: vk::ShaderStageFlags::empty(),
+                offset: info.offset,
+                size: 0,
+            });
+
+            let end = (range.offset + range.size).max(info.offset + info.size);
+            range.offset = range.offset.min(info.offset);
+            range.size = end - range.offset;
+            range.stage_flags |= *stage;
+        }
+    }
+
+    Ok(merged)
+}
+
+//================================================
+// Layout Cache
+//================================================
+
+type PipelineLayoutKey = (Vec<vk::DescriptorSetLayout>, Vec<vk::PushConstantRange>);
+
+/// Creates descriptor set layouts and pipeline layouts once and hands out the same handles for
+/// the same bindings.
+#[derive(Clone, Debug, Default)]
+pub struct LayoutCache {
+    set_layouts: HashMap<Vec<DescriptorBinding>, vk::DescriptorSetLayout>,
+    pipeline_layouts: HashMap<PipelineLayoutKey, vk::PipelineLayout>,
+}
+
+impl LayoutCache {
+    /// Returns the descriptor set layout with the given bindings, creating it if needed.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every cached layout was created with.
+    pub unsafe fn set_layout(
+        &mut self,
+        device: &Device,
+        bindings: &[DescriptorBinding],
+    ) -> Result<vk::DescriptorSetLayout> {
+        let mut key = bindings.to_vec();
+        key.sort_by_key(|b| b.binding);
+
+        if let Some(layout) = self.set_layouts.get(&key) {
+            return Ok(*layout);
+        }
+
+        let bindings = key
+            .iter()
+            .map(|b| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(b.binding)
+                    .descriptor_type(b.descriptor_type)
+                    .descriptor_count(b.count)
+                    .stage_flags(b.stages)
+            })
+            .collect::<Vec<_>>();
+
+        let info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+        let layout = device.create_descriptor_set_layout(&info, None)?;
+
+        self.set_layouts.insert(key, layout);
+        Ok(layout)
+    }
+
+    /// Returns the pipeline layout with the given descriptor set layouts and push constant
+    /// ranges, creating it if needed.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every cached layout was created with.
+    pub unsafe fn pipeline_layout(
+        &mut self,
+        device: &Device,
+        set_layouts: &[vk::DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> Result<vk::PipelineLayout> {
+        let key = (set_layouts.to_vec(), push_constant_ranges.to_vec());
+        if let Some(layout) = self.pipeline_layouts.get(&key) {
+            return Ok(*layout);
+        }
+
+        let info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(set_layouts)
+            .push_constant_ranges(push_constant_ranges);
+        let layout = device.create_pipeline_layout(&info, None)?;
+
+        self.pipeline_layouts.insert(key, layout);
+        Ok(layout)
+    }
+
+    /// Returns the pipeline layout for a set of shader stages, generated from their reflected
+    /// descriptor bindings, with the push constant range of a
+    /// [`PushConstants`](super::PushConstants) type if the shaders use push constants.
+    ///
+    /// The frame set always uses the [`FRAME_BINDINGS`] layout, and a shader that declares it
+    /// differently is an error. Unused sets below the highest used set get empty layouts.
+    /// Shaders that use push constants without a registered range, or a larger one, are errors
+    /// too.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every cached layout was created with.
+    pub unsafe fn reflect_pipeline_layout(
+        &mut self,
+        device: &Device,
+        shaders: &[(&[u8], vk::ShaderStageFlags)],
+        push_constants: Option<vk::PushConstantRange>,
+    ) -> Result<vk::PipelineLayout> {
+        let sets = reflect_descriptor_sets(shaders)?;
+        let reflected = reflect_push_constant_range(shaders)?;
+
+        let push_constant_ranges = match (reflected, push_constants) {
+            (None, _) => vec![],
+            (Some(reflected), None) => {
+                return Err(anyhow!(
+                    "Shaders use {} bytes of push constants, but no push constant type was \
+                     registered.",
+                    reflected.size
+                ));
+            }
+            (Some(reflected), Some(range)) => {
+                if reflected.offset + reflected.size > range.offset + range.size
+                    || !range.stage_flags.contains(reflected.stage_flags)
+                {
+                    return Err(anyhow!(
+                        "Shader push constants {reflected:?} do not fit the registered {range:?}."
+                    ));
+                }
+
+                vec![range]
+            }
+        };
+
+        if let Some(bindings) = sets.get(&FRAME_SET)
+            && !bindings.iter().all(|b| {
+                FRAME_BINDINGS.iter().any(|f| {
+                    f.binding == b.binding
+                        && f.descriptor_type == b.descriptor_type
+                        && f.count == b.count
+                })
+            })
+        {
+            return Err(anyhow!(
+                "Shader frame set {bindings:?} does not match the frame constants layout."
+            ));
+        }
+
+        let count = sets.keys().next_back().map_or(0, |s| s + 1);
+        let set_layouts = (0..count)
+            .map(|set| match set {
+                FRAME_SET => self.set_layout(device, FRAME_BINDINGS),
+                _ => self.set_layout(device, sets.get(&set).map_or(&[], |b| b)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.pipeline_layout(device, &set_layouts, &push_constant_ranges)
+    }
+
+    /// Destroys every cached layout.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every cached layout was created with, and the layouts must no
+    /// longer be in use.
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for (_, layout) in self.pipeline_layouts.drain() {
+            device.destroy_pipeline_layout(layout, None);
+        }
+
+        for (_, layout) in self.set_layouts.drain() {
+            device.destroy_descriptor_set_layout(layout, None);
+        }
+    }
+}
+
+//================================================
+// Descriptor Allocator
+//================================================
+
+/// The number of descriptor sets each pool of a [`DescriptorAllocator`] has room for.
+const SETS_PER_POOL: u32 = 64;
+
+/// The number of descriptors of each type each pool of a [`DescriptorAllocator`] has room for.
+const POOL_SIZES: &[(vk::DescriptorType, u32)] = &[
+    (vk::DescriptorType::UNIFORM_BUFFER, SETS_PER_POOL),
+    (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, SETS_PER_POOL),
+    (vk::DescriptorType::STORAGE_BUFFER, SETS_PER_POOL / 4),
+    (vk::DescriptorType::SAMPLED_IMAGE, SETS_PER_POOL / 4),
+    (vk::DescriptorType::STORAGE_IMAGE, SETS_PER_POOL / 4),
+    (vk::DescriptorType::SAMPLER, SETS_PER_POOL / 4),
+];
+
+/// Allocates descriptor sets from a growing list of descriptor pools, creating a new pool
+/// whenever the current ones run out of room.
+#[derive(Clone, Debug, Default)]
+pub struct DescriptorAllocator {
+    pools: Vec<vk::DescriptorPool>,
+    owners: HashMap<vk::DescriptorSet, vk::DescriptorPool>,
+}
+
+impl DescriptorAllocator {
+    /// Allocates a descriptor set with a layout.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every pool was created with.
+    pub unsafe fn allocate(
+        &mut self,
+        device: &Device,
+        layout: vk::DescriptorSetLayout,
+    ) -> Result<vk::DescriptorSet> {
+        let layouts = &[layout];
+
+        if let Some(pool) = self.pools.last().cloned() {
+            let info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(pool)
+                .set_layouts(layouts);
+
+            match device.allocate_descriptor_sets(&info) {
+                Ok(sets) => return Ok(self.track(sets[0], pool)),
+                Err(vk::ErrorCode::OUT_OF_POOL_MEMORY | vk::ErrorCode::FRAGMENTED_POOL) => {}
+                Err(e) => return Err(anyhow!(e)),
+            }
+        }
+
+        let pool = self.create_pool(device)?;
+        let info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(pool)
+            .set_layouts(layouts);
+
+        let set = device.allocate_descriptor_sets(&info)?[0];
+        Ok(self.track(set, pool))
+    }
+
+    /// Frees a descriptor set allocated by this allocator.
+    ///
+    /// # Safety
+    ///
+    /// The descriptor set must no longer be in use by the GPU.
+    pub unsafe fn free(&mut self, device: &Device, set: vk::DescriptorSet) -> Result<()> {
+        let pool = self.owners.remove(&set).ok_or_else(|| {
+            anyhow!("Descriptor set {set:?} was not allocated by this allocator.")
+        })?;
+
+        device.free_descriptor_sets(pool, &[set])?;
+
+        Ok(())
+    }
+
+    /// Destroys every pool, freeing every descriptor set allocated from them.
+    ///
+    /// # Safety
+    ///
+    /// The descriptor sets must no longer be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for pool in self.pools.drain(..) {
+            device.destroy_descriptor_pool(pool, None);
+        }
+
+        self.owners.clear();
+    }
+
+    fn track(&mut self, set: vk::DescriptorSet, pool: vk::DescriptorPool) -> vk::DescriptorSet {
+        self.owners.insert(set, pool);
+        set
+    }
+
+    unsafe fn create_pool(&mut self, device: &Device) -> Result<vk::DescriptorPool> {
+        let pool_sizes = POOL_SIZES
+            .iter()
+            .map(|(type_, count)| {
+                vk::DescriptorPoolSize::builder()
+                    .type_(*type_)
+                    .descriptor_count(*count)
+            })
+            .collect::<Vec<_>>();
+
+        let info = vk::DescriptorPoolCreateInfo::builder()
+            .flags(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+            .pool_sizes(&pool_sizes)
+            .max_sets(SETS_PER_POOL);
+
+        let pool = device.create_descriptor_pool(&info, None)?;
+
+        self.pools.push(pool);
+        Ok(pool)
+    }
+}
+
+//================================================
+// Descriptor Set Builder
+//================================================
+
+/// Builds a descriptor set and its layout from the resources bound to it.
+///
+/// ```ignore
+/// let set = DescriptorSetBuilder::new()
+///     .uniform_buffer(0, buffer, size, vk::ShaderStageFlags::VERTEX)
+///     .combined_image_sampler(1, view, sampler, vk::ShaderStageFlags::FRAGMENT)
+///     .build(device, data)?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DescriptorSetBuilder {
+    bindings: Vec<DescriptorBinding>,
+    buffers: Vec<(u32, vk::DescriptorType, vk::DescriptorBufferInfo)>,
+    images: Vec<(u32, vk::DescriptorType, vk::DescriptorImageInfo)>,
+}
+
+impl DescriptorSetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `range` bytes at the start of a uniform buffer.
+    pub fn uniform_buffer(
+        self,
+        binding: u32,
+        buffer: vk::Buffer,
+        range: vk::DeviceSize,
+        stages: vk::ShaderStageFlags,
+    ) -> Self {
+        self.buffer(
+            binding,
+            vk::DescriptorType::UNIFORM_BUFFER,
+            buffer,
+            range,
+            stages,
+        )
+    }
+
+    /// Binds `range` bytes at the start of a storage buffer.
+    pub fn storage_buffer(
+        self,
+        binding: u32,
+        buffer: vk::Buffer,
+        range: vk::DeviceSize,
+        stages: vk::ShaderStageFlags,
+    ) -> Self {
+        self.buffer(
+            binding,
+            vk::DescriptorType::STORAGE_BUFFER,
+            buffer,
+            range,
+            stages,
+        )
+    }
+
+    /// Binds an image view in `SHADER_READ_ONLY_OPTIMAL` with a sampler.
+    pub fn combined_image_sampler(
+        mut self,
+        binding: u32,
+        image_view: vk::ImageView,
+        sampler: vk::Sampler,
+        stages: vk::ShaderStageFlags,
+    ) -> Self {
+        self.bindings.push(DescriptorBinding {
+            binding,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            count: 1,
+            stages,
+        });
+
+        let info = vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(image_view)
+            .sampler(sampler)
+            .build();
+
+        self.images
+            .push((binding, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, info));
+        self
+    }
+
+    fn buffer(
+        mut self,
+        binding: u32,
+        descriptor_type: vk::DescriptorType,
+        buffer: vk::Buffer,
+        range: vk::DeviceSize,
+        stages: vk::ShaderStageFlags,
+    ) -> Self {
+        self.bindings.push(DescriptorBinding {
+            binding,
+            descriptor_type,
+            count: 1,
+            stages,
+        });
+
+        let info = vk::DescriptorBufferInfo::builder()
+            .buffer(buffer)
+            .offset(0)
+            .range(range)
+            .build();
+
+        self.buffers.push((binding, descriptor_type, info));
+        self
+    }
+
+    /// Returns the layout of the set from the layout cache.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device.
+    pub unsafe fn layout(
+        &self,
+        device: &Device,
+        data: &mut ContextData,
+    ) -> Result<vk::DescriptorSetLayout> {
+        data.layouts.set_layout(device, &self.bindings)
+    }
+
+    /// Allocates the set and writes the bound resources into it.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device, and the bound resources must outlive the
+    /// set.
+    pub unsafe fn build(
+        &self,
+        device: &Device,
+        data: &mut ContextData,
+    ) -> Result<vk::DescriptorSet> {
+        let layout = self.layout(device, data)?;
+        let set = data.descriptors.allocate(device, layout)?;
+        self.write(device, set);
+        Ok(set)
+    }
+
+    /// Writes the bound resources into an existing set with the same layout.
+    ///
+    /// # Safety
+    ///
+    /// The set must not be in use by the GPU.
+    pub unsafe fn write(&self, device: &Device, set: vk::DescriptorSet) {
+        let buffer_writes = self.buffers.iter().map(|(binding, descriptor_type, info)| {
+            vk::WriteDescriptorSet::builder()
+                .dst_set(set)
+                .dst_binding(*binding)
+                .dst_array_element(0)
+                .descriptor_type(*descriptor_type)
+                .buffer_info(std::slice::from_ref(info))
+        });
+
+        let image_writes = self.images.iter().map(|(binding, descriptor_type, info)| {
+            vk::WriteDescriptorSet::builder()
+                .dst_set(set)
+                .dst_binding(*binding)
+                .dst_array_element(0)
+                .descriptor_type(*descriptor_type)
+                .image_info(std::slice::from_ref(info))
+        });
+
+        let writes = buffer_writes.chain(image_writes).collect::<Vec<_>>();
+        device.update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assembles a SPIR-V module with a push constant block of `vec4` members, or without push
+    /// constants for zero members.
+    fn spirv(members: u32) -> Vec<u8> {
+        const FLOAT: u32 = 1;
+        const BLOCK: u32 = 2;
+        const VEC4: u32 = 3;
+        const POINTER: u32 = 4;
+        const VARIABLE: u32 = 5;
+        const PUSH_CONSTANT: u32 = 9;
+        const OFFSET: u32 = 35;
+
+        let op = |opcode: u32, operands: &[u32]| {
+            let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+            words.extend_from_slice(operands);
+            words
+        };
+
+        let mut words = vec![0x07230203, 0x00010000, 0, VARIABLE + 1, 0];
+        words.extend(op(17, &[1])); // OpCapability Shader
+        words.extend(op(14, &[0, 1])); // OpMemoryModel Logical GLSL450
+
+        if members > 0 {
+            for m in 0..members {
+                words.extend(op(72, &[BLOCK, m, OFFSET, m * 16])); // OpMemberDecorate
+            }
+            words.extend(op(22, &[FLOAT, 32])); // OpTypeFloat
+            words.extend(op(23, &[VEC4, FLOAT, 4])); // OpTypeVector
+            let mut block = vec![BLOCK];
+            block.extend((0..members).map(|_| VEC4));
+            words.extend(op(30, &block)); // OpTypeStruct
+            words.extend(op(32, &[POINTER, PUSH_CONSTANT, BLOCK])); // OpTypePointer
+            words.extend(op(59, &[POINTER, VARIABLE, PUSH_CONSTANT])); // OpVariable
+        }
+
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn no_push_constants() {
+        let module = spirv(0);
+        let range =
+            reflect_push_constant_range(&[(&module, vk::ShaderStageFlags::VERTEX)]).unwrap();
+        assert_eq!(range, None);
+    }
+
+    #[test]
+    fn merges_push_constant_ranges() {
+        let (vertex, fragment, none) = (spirv(2), spirv(4), spirv(0));
+        let range = reflect_push_constant_range(&[
+            (&vertex, vk::ShaderStageFlags::VERTEX),
+            (&fragment, vk::ShaderStageFlags::FRAGMENT),
+            (&none, vk::ShaderStageFlags::GEOMETRY),
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(range.offset, 0);
+        assert_eq!(range.size, 64);
+        assert_eq!(
+            range.stage_flags,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! Physical device selection and logical device creation.
+
+use std::collections::HashSet;
+
+use anyhow::{Result, anyhow};
+use log::*;
+use thiserror::Error;
+use vulkanalia::{prelude::v1_0::*, vk::KhrSurfaceExtension};
+
+use super::{
+    ContextData, PORTABILITY_MACOS_VERSION, debug::VALIDATION_LAYER, sampler::SamplerCache,
+    swapchain::SwapchainSupport,
+};
+
+/// The required device extensions.
+pub const DEVICE_EXTENSIONS: &[vk::ExtensionName] = &[vk::KHR_SWAPCHAIN_EXTENSION.name];
+
+/// Device extensions that are not required but make a device preferable when scoring, since
+/// they expose memory budgets and newer rendering and synchronization paths.
+pub const OPTIONAL_DEVICE_EXTENSIONS: &[vk::ExtensionName] = &[
+    vk::EXT_MEMORY_BUDGET_EXTENSION.name,
+    vk::KHR_DYNAMIC_RENDERING_EXTENSION.name,
+    vk::KHR_SYNCHRONIZATION2_EXTENSION.name,
+    vk::KHR_TIMELINE_SEMAPHORE_EXTENSION.name,
+];
+
+//================================================
+// Physical Device
+//================================================
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct SuitabilityError(pub &'static str);
+
+/// Overrides the physical device picked by a [`DeviceSelector`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceOverride {
+    /// The index of the device in the order the devices are enumerated.
+    Index(usize),
+    /// A case-insensitive substring of the device name.
+    Name(String),
+}
+
+impl DeviceOverride {
+    /// Parses an override from a device index or a device name.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(value.to_string()),
+        }
+    }
+
+    fn matches(&self, index: usize, name: &str) -> bool {
+        match self {
+            Self::Index(i) => *i == index,
+            Self::Name(n) => name.to_lowercase().contains(&n.to_lowercase()),
+        }
+    }
+}
+
+/// Picks the physical device to render with.
+///
+/// Every suitable device is scored by its type, queue families and device local memory, and the
+/// highest scoring device is picked. The `VULKANRS_GPU` environment variable (a device index or
+/// name) takes priority over an override set with [`DeviceSelector::set_override`].
+#[derive(Clone, Debug, Default)]
+pub struct DeviceSelector {
+    device_override: Option<DeviceOverride>,
+}
+
+impl DeviceSelector {
+    /// Creates a selector that picks the highest scoring device.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a selector that picks a specific device when it is suitable.
+    pub fn with_override(device_override: DeviceOverride) -> Self {
+        Self {
+            device_override: Some(device_override),
+        }
+    }
+
+    /// Sets or clears the device override.
+    pub fn set_override(&mut self, device_override: Option<DeviceOverride>) {
+        self.device_override = device_override;
+    }
+
+    /// Returns the device override, preferring the one from `VULKANRS_GPU`.
+    pub fn device_override(&self) -> Option<DeviceOverride> {
+        match std::env::var("VULKANRS_GPU") {
+            Ok(value) if !value.trim().is_empty() => Some(DeviceOverride::parse(&value)),
+            _ => self.device_override.clone(),
+        }
+    }
+
+    unsafe fn select(&self, instance: &Instance, data: &ContextData) -> Result<vk::PhysicalDevice> {
+        let device_override = self.device_override();
+        let mut best = None;
+        let mut overridden = None;
+
+        for (index, physical_device) in instance
+            .enumerate_physical_devices()?
+            .into_iter()
+            .enumerate()
+        {
+            let properties = instance.get_physical_device_properties(physical_device);
+            let name = properties.device_name.to_string_lossy();
+
+            if let Err(error) = check_physical_device(instance, data, physical_device) {
+                warn!("Skipping physical device {index} (`{name}`): {error}");
+                continue;
+            }
+
+            let score = score_physical_device(instance, data, physical_device)?;
+            info!(
+                "Found physical device {index} (`{name}`, {:?}) with score {score}.",
+                properties.device_type
+            );
+
+            if device_override
+                .as_ref()
+                .is_some_and(|o| overridden.is_none() && o.matches(index, &name))
+            {
+                overridden = Some(physical_device);
+            }
+
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((physical_device, score));
+            }
+        }
+
+        if let Some(physical_device) = overridden {
+            return Ok(physical_device);
+        } else if let Some(device_override) = device_override {
+            warn!("No suitable physical device matches {device_override:?}, ignoring override.");
+        }
+
+        best.map(|(d, _)| d)
+            .ok_or_else(|| anyhow!("Failed to find suitable physical device."))
+    }
+}
+
+pub(crate) unsafe fn pick_physical_device(
+    instance: &Instance,
+    data: &mut ContextData,
+    selector: &DeviceSelector,
+) -> Result<()> {
+    data.physical_device = selector.select(instance, data)?;
+
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    info!(
+        "Selected physical device (`{}`, {:?}, Vulkan {}).",
+        properties.device_name,
+        properties.device_type,
+        vulkanalia::Version::from(properties.api_version),
+    );
+
+    Ok(())
+}
+
+/// Scores a suitable physical device, where higher scores are preferred.
+unsafe fn score_physical_device(
+    instance: &Instance,
+    data: &ContextData,
+    physical_device: vk::PhysicalDevice,
+) -> Result<u64> {
+    let properties = instance.get_physical_device_properties(physical_device);
+    let memory = instance.get_physical_device_memory_properties(physical_device);
+    let indices = QueueFamilyIndices::get(instance, data, physical_device)?;
+
+    let mut score = match properties.device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 10_000,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 5_000,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2_000,
+        vk::PhysicalDeviceType::CPU => 1_000,
+        _ => 0,
+    };
+
+    // Graphics and presentation on the same queue family avoids concurrent swapchain images.
+    if indices.graphics == indices.present {
+        score += 500;
+    }
+
+    // 100 points per supported optional extension, so extensions only decide between devices
+    // of the same type.
+    let extensions = get_device_extensions(instance, physical_device)?;
+    score += 100
+        * OPTIONAL_DEVICE_EXTENSIONS
+            .iter()
+            .filter(|e| extensions.contains(e))
+            .count() as u64;
+
+    // One point per 64 MiB of device local memory.
+    let device_local = memory.memory_heaps[..memory.memory_heap_count as usize]
+        .iter()
+        .filter(|h| h.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+        .map(|h| h.size)
+        .sum::<u64>();
+    score += device_local / (64 * 1024 * 1024);
+
+    Ok(score)
+}
+
+unsafe fn check_physical_device(
+    instance: &Instance,
+    data: &ContextData,
+    physical_device: vk::PhysicalDevice,
+) -> Result<()> {
+    QueueFamilyIndices::get(instance, data, physical_device)?;
+    check_physical_device_extensions(instance, physical_device)?;
+
+    let support = SwapchainSupport::get(instance, data, physical_device)?;
+    if support.formats.is_empty() || support.present_modes.is_empty() {
+        return Err(anyhow!(SuitabilityError("Insufficient swapchain support.")));
+    }
+
+    Ok(())
+}
+
+unsafe fn check_physical_device_extensions(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+) -> Result<()> {
+    let extensions = get_device_extensions(instance, physical_device)?;
+    if DEVICE_EXTENSIONS.iter().all(|e| extensions.contains(e)) {
+        Ok(())
+    } else {
+        Err(anyhow!(SuitabilityError(
+            "Missing required device extensions."
+        )))
+    }
+}
+
+/// Returns the names of the extensions a physical device supports.
+unsafe fn get_device_extensions(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+) -> Result<HashSet<vk::ExtensionName>> {
+    Ok(instance
+        .enumerate_device_extension_properties(physical_device, None)?
+        .iter()
+        .map(|e| e.extension_name)
+        .collect())
+}
+
+//================================================
+// Logical Device
+//================================================
+
+pub(crate) unsafe fn create_logical_device(
+    entry: &Entry,
+    instance: &Instance,
+    data: &mut ContextData,
+) -> Result<Device> {
+    // Queue Create Infos
+
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+
+    let mut unique_indices = HashSet::new();
+    unique_indices.insert(indices.graphics);
+    unique_indices.insert(indices.present);
+
+    let queue_priorities = &[1.0];
+    let queue_infos = unique_indices
+        .iter()
+        .map(|i| {
+            vk::DeviceQueueCreateInfo::builder()
+                .queue_family_index(*i)
+                .queue_priorities(queue_priorities)
+        })
+        .collect::<Vec<_>>();
+
+    // Layers
+
+    let layers = if data.debug.enabled {
+        vec![VALIDATION_LAYER.as_ptr()]
+    } else {
+        vec![]
+    };
+
+    // Extensions
+
+    let mut extensions = DEVICE_EXTENSIONS
+        .iter()
+        .map(|n| n.as_ptr())
+        .collect::<Vec<_>>();
+
+    // Required by Vulkan SDK on macOS since 1.3.216.
+    if cfg!(target_os = "macos") && entry.version()? >= PORTABILITY_MACOS_VERSION {
+        extensions.push(vk::KHR_PORTABILITY_SUBSET_EXTENSION.name.as_ptr());
+    }
+
+    // Features
+
+    let supported = instance.get_physical_device_features(data.physical_device);
+    let anisotropy = supported.sampler_anisotropy == vk::TRUE;
+
+    let features = vk::PhysicalDeviceFeatures::builder().sampler_anisotropy(anisotropy);
+
+    // Create
+
+    let info = vk::DeviceCreateInfo::builder()
+        .queue_create_infos(&queue_infos)
+        .enabled_layer_names(&layers)
+        .enabled_extension_names(&extensions)
+        .enabled_features(&features);
+
+    let device = instance.create_device(data.physical_device, &info, None)?;
+
+    // Queues
+
+    data.graphics_queue = device.get_device_queue(indices.graphics, 0);
+    data.present_queue = device.get_device_queue(indices.present, 0);
+
+    // Samplers
+
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    data.samplers = SamplerCache::new(if anisotropy {
+        properties.limits.max_sampler_anisotropy
+    } else {
+        1.0
+    });
+
+    Ok(device)
+}
+
+//================================================
+// Structs
+//================================================
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct QueueFamilyIndices {
+    pub graphics: u32,
+    pub present: u32,
+}
+
+impl QueueFamilyIndices {
+    pub unsafe fn get(
+        instance: &Instance,
+        data: &ContextData,
+        physical_device: vk::PhysicalDevice,
+    ) -> Result<Self> {
+        let properties = instance.get_physical_device_queue_family_properties(physical_device);
+
+        let graphics = properties
+            .iter()
+            .position(|p| p.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+            .map(|i| i as u32);
+
+        let mut present = None;
+        for (index, properties) in properties.iter().enumerate() {
+            if instance.get_physical_device_surface_support_khr(
+                physical_device,
+                index as u32,
+                data.surface,
+            )? {
+                present = Some(index as u32);
+                break;
+            }
+        }
+
+        if let (Some(graphics), Some(present)) = (graphics, present) {
+            Ok(Self { graphics, present })
+        } else {
+            Err(anyhow!(SuitabilityError(
+                "Missing required queue families."
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_override() {
+        assert_eq!(DeviceOverride::parse("1"), DeviceOverride::Index(1));
+        assert_eq!(DeviceOverride::parse(" 2 "), DeviceOverride::Index(2));
+        assert_eq!(
+            DeviceOverride::parse(" RTX 4090 "),
+            DeviceOverride::Name(String::from("RTX 4090"))
+        );
+        assert_eq!(
+            DeviceOverride::parse("-1"),
+            DeviceOverride::Name(String::from("-1"))
+        );
+    }
+
+    #[test]
+    fn override_matches() {
+        assert!(DeviceOverride::Index(1).matches(1, "Any"));
+        assert!(!DeviceOverride::Index(1).matches(0, "Any"));
+        assert!(DeviceOverride::parse("geforce").matches(0, "NVIDIA GeForce RTX 4090"));
+        assert!(!DeviceOverride::parse("radeon").matches(0, "NVIDIA GeForce RTX 4090"));
+    }
+}
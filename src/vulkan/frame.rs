@@ -0,0 +1,155 @@
+//! Per-frame resources for each frame that can be processed concurrently.
+
+use anyhow::{Result, anyhow};
+use vulkanalia::prelude::v1_0::*;
+
+use super::{ContextData, FrameConstants, UniformBuffer, descriptor::DescriptorSetBuilder};
+
+/// The default number of frames that can be processed concurrently.
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+/// The maximum number of frames that can be processed concurrently.
+pub const MAX_FRAMES_IN_FLIGHT: usize = 3;
+
+/// The resources used to record, submit and present a single frame.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameResources {
+    pub command_buffer: vk::CommandBuffer,
+    pub image_available_semaphore: vk::Semaphore,
+    pub render_finished_semaphore: vk::Semaphore,
+    pub in_flight_fence: vk::Fence,
+    /// The descriptor set for the [`FRAME_SET`](super::descriptor::FRAME_SET) slot, which binds
+    /// the buffer of the frame in [`FramesInFlight::constants`].
+    pub descriptor_set: vk::DescriptorSet,
+}
+
+/// Owns one set of [`FrameResources`] and one [`FrameConstants`] buffer for each frame in
+/// flight and tracks which one is in use.
+#[derive(Clone, Debug, Default)]
+pub struct FramesInFlight {
+    frames: Vec<FrameResources>,
+    constants: UniformBuffer<FrameConstants>,
+    current: usize,
+}
+
+impl FramesInFlight {
+    /// Creates the resources for `count` frames in flight, which must be between 1 and
+    /// [`MAX_FRAMES_IN_FLIGHT`].
+    pub(crate) unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        data: &mut ContextData,
+        count: usize,
+    ) -> Result<Self> {
+        if !(1..=MAX_FRAMES_IN_FLIGHT).contains(&count) {
+            return Err(anyhow!(
+                "Frames in flight must be between 1 and {MAX_FRAMES_IN_FLIGHT}, got {count}."
+            ));
+        }
+
+        // Command Buffers
+
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(data.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(count as u32);
+
+        let command_buffers = device.allocate_command_buffers(&allocate_info)?;
+
+        // Frame Constants
+
+        let constants = UniformBuffer::create(instance, device, data, count)?;
+
+        // Sync Objects
+
+        let semaphore_info = vk::SemaphoreCreateInfo::builder();
+        let fence_info = vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED);
+
+        let frames = command_buffers
+            .into_iter()
+            .enumerate()
+            .map(|(i, command_buffer)| {
+                let descriptor_set = DescriptorSetBuilder::new()
+                    .uniform_buffer(
+                        0,
+                        constants.buffer(i),
+                        UniformBuffer::<FrameConstants>::SIZE,
+                        vk::ShaderStageFlags::ALL_GRAPHICS,
+                    )
+                    .build(device, data)?;
+
+                Ok(FrameResources {
+                    command_buffer,
+                    image_available_semaphore: device.create_semaphore(&semaphore_info, None)?,
+                    render_finished_semaphore: device.create_semaphore(&semaphore_info, None)?,
+                    in_flight_fence: device.create_fence(&fence_info, None)?,
+                    descriptor_set,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            frames,
+            constants,
+            current: 0,
+        })
+    }
+
+    /// Returns the number of frames in flight.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns whether there are no frames in flight, which is only the case before creation.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the index of the frame currently being recorded.
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the resources of the frame currently being recorded.
+    pub fn current(&self) -> &FrameResources {
+        &self.frames[self.current]
+    }
+
+    /// Returns the resources of a frame.
+    pub fn get(&self, index: usize) -> &FrameResources {
+        &self.frames[index]
+    }
+
+    /// Returns the buffer holding the [`FrameConstants`] of each frame, indexed like the frames.
+    pub fn constants(&self) -> &UniformBuffer<FrameConstants> {
+        &self.constants
+    }
+
+    /// Returns the index of the frame that signals a fence, if any.
+    pub fn position_of_fence(&self, fence: vk::Fence) -> Option<usize> {
+        self.frames.iter().position(|f| f.in_flight_fence == fence)
+    }
+
+    /// Moves on to the next frame.
+    pub fn advance(&mut self) {
+        self.current = (self.current + 1) % self.frames.len();
+    }
+
+    /// Destroys the resources of every frame. The descriptor sets are freed with the pools of the
+    /// [`DescriptorAllocator`](super::DescriptorAllocator).
+    #[rustfmt::skip]
+    pub(crate) unsafe fn destroy(&mut self, device: &Device, command_pool: vk::CommandPool) {
+        let command_buffers = self.frames.iter().map(|f| f.command_buffer).collect::<Vec<_>>();
+        device.free_command_buffers(command_pool, &command_buffers);
+
+        self.constants.destroy(device);
+
+        for frame in self.frames.drain(..) {
+            device.destroy_fence(frame.in_flight_fence, None);
+            device.destroy_semaphore(frame.render_finished_semaphore, None);
+            device.destroy_semaphore(frame.image_available_semaphore, None);
+        }
+
+        self.current = 0;
+    }
+}
@@ -0,0 +1,29 @@
+//! The framebuffer of the render target, sharing the depth and multisampled color attachments.
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{ContextData, msaa::is_msaa_enabled};
+
+pub(crate) unsafe fn create_framebuffer(device: &Device, data: &mut ContextData) -> Result<()> {
+    let attachments = if is_msaa_enabled(data) {
+        vec![
+            data.color_image_view,
+            data.depth_image_view,
+            data.target_image_view,
+        ]
+    } else {
+        vec![data.target_image_view, data.depth_image_view]
+    };
+
+    let create_info = vk::FramebufferCreateInfo::builder()
+        .render_pass(data.render_pass)
+        .attachments(&attachments)
+        .width(data.render_extent.width)
+        .height(data.render_extent.height)
+        .layers(1);
+
+    data.framebuffer = device.create_framebuffer(&create_info, None)?;
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Vulkan instance creation.
+
+use std::collections::HashSet;
+
+use anyhow::{Result, anyhow};
+use log::*;
+use vulkanalia::{prelude::v1_0::*, window as vk_window};
+use winit::window::Window as WinitWindow;
+
+use super::{
+    ContextData, PORTABILITY_MACOS_VERSION,
+    debug::{VALIDATION_LAYER, create_debug_messenger, get_debug_messenger_info},
+};
+
+pub(crate) unsafe fn create_instance(
+    window: &WinitWindow,
+    entry: &Entry,
+    data: &mut ContextData,
+) -> Result<Instance> {
+    // Application Info
+
+    let application_info = vk::ApplicationInfo::builder()
+        .application_name(b"Vulkan Tutorial (Rust)\0")
+        .application_version(vk::make_version(1, 0, 0))
+        .engine_name(b"No Engine\0")
+        .engine_version(vk::make_version(1, 0, 0))
+        .api_version(vk::make_version(1, 0, 0));
+
+    // Layers
+
+    let available_layers = entry
+        .enumerate_instance_layer_properties()?
+        .iter()
+        .map(|l| l.layer_name)
+        .collect::<HashSet<_>>();
+
+    let validation_enabled = data.debug.enabled;
+
+    if validation_enabled && !available_layers.contains(&VALIDATION_LAYER) {
+        return Err(anyhow!("Validation layer requested but not supported."));
+    }
+
+    let layers = if validation_enabled {
+        vec![VALIDATION_LAYER.as_ptr()]
+    } else {
+        Vec::new()
+    };
+
+    // Extensions
+
+    let mut extensions = vk_window::get_required_instance_extensions(window)
+        .iter()
+        .map(|e| e.as_ptr())
+        .collect::<Vec<_>>();
+
+    // Required by Vulkan SDK on macOS since 1.3.216.
+    let flags = if cfg!(target_os = "macos") && entry.version()? >= PORTABILITY_MACOS_VERSION {
+        info!("Enabling extensions for macOS portability.");
+        extensions.push(
+            vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_EXTENSION
+                .name
+                .as_ptr(),
+        );
+        extensions.push(vk::KHR_PORTABILITY_ENUMERATION_EXTENSION.name.as_ptr());
+        vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR
+    } else {
+        vk::InstanceCreateFlags::empty()
+    };
+
+    if validation_enabled {
+        extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION.name.as_ptr());
+    }
+
+    // Create
+
+    let mut info = vk::InstanceCreateInfo::builder()
+        .application_info(&application_info)
+        .enabled_layer_names(&layers)
+        .enabled_extension_names(&extensions)
+        .flags(flags);
+
+    let mut debug_info = get_debug_messenger_info(&data.debug);
+
+    if validation_enabled {
+        info = info.push_next(&mut debug_info);
+    }
+
+    let instance = entry.create_instance(&info, None)?;
+
+    // Messenger
+
+    if validation_enabled {
+        create_debug_messenger(&instance, data)?;
+    }
+
+    Ok(instance)
+}
@@ -0,0 +1,275 @@
+//! The Vulkan context and the functions that create the objects it owns.
+
+pub mod buffer;
+pub(crate) mod command;
+pub mod constants;
+pub mod debug;
+pub(crate) mod depth;
+pub mod descriptor;
+pub(crate) mod device;
+pub mod frame;
+pub(crate) mod framebuffer;
+pub(crate) mod image;
+pub(crate) mod instance;
+pub(crate) mod memory;
+pub(crate) mod msaa;
+pub(crate) mod pipeline;
+pub mod push;
+pub mod sampler;
+pub(crate) mod swapchain;
+pub(crate) mod sync;
+pub(crate) mod target;
+pub mod texture;
+pub mod vertex;
+
+use anyhow::{Result, anyhow};
+use vulkanalia::{
+    Version,
+    loader::{LIBRARY, LibloadingLoader},
+    prelude::v1_0::*,
+    vk::{ExtDebugUtilsExtension, KhrSurfaceExtension, KhrSwapchainExtension},
+    window as vk_window,
+};
+use winit::window::Window as WinitWindow;
+
+pub use self::{
+    buffer::{Index, IndexBuffer, UniformBuffer, VertexBuffer},
+    constants::FrameConstants,
+    debug::DebugConfig,
+    descriptor::{DescriptorAllocator, DescriptorSetBuilder, LayoutCache},
+    device::{DeviceOverride, DeviceSelector, SuitabilityError},
+    frame::{DEFAULT_FRAMES_IN_FLIGHT, FrameResources, FramesInFlight, MAX_FRAMES_IN_FLIGHT},
+    push::{ObjectConstants, PushConstants, cmd_push_constants},
+    sampler::{SamplerCache, SamplerDesc},
+    swapchain::PresentModePreference,
+    sync::GpuHangError,
+    target::{MAX_RENDER_SCALE, MIN_RENDER_SCALE},
+    texture::{Texture2D, TextureOptions, TextureSource},
+    vertex::{ColorVertex, MeshVertex, Vertex, VertexAttribute, VertexLayout},
+};
+use self::{
+    command::create_command_pool,
+    depth::create_depth_objects,
+    device::{create_logical_device, pick_physical_device},
+    framebuffer::create_framebuffer,
+    instance::create_instance,
+    msaa::{create_color_objects, destroy_color_objects, select_msaa_samples},
+    pipeline::{TRIANGLE_INDICES, TRIANGLE_VERTICES, create_pipeline, create_render_pass},
+    swapchain::create_swapchain,
+    target::{create_target_objects, get_render_extent},
+};
+use crate::renderer::RendererConfig;
+
+/// The Vulkan SDK version that started requiring the portability subset extension for macOS.
+pub const PORTABILITY_MACOS_VERSION: Version = Version::new(1, 3, 216);
+
+/// The Vulkan entry point, instance and logical device along with every object created from them.
+#[derive(Clone, Debug)]
+pub struct Context {
+    pub entry: Entry,
+    pub instance: Instance,
+    pub device: Device,
+    pub data: ContextData,
+}
+
+impl Context {
+    /// Creates the Vulkan context for a window.
+    ///
+    /// # Safety
+    ///
+    /// The window must outlive the returned context, and [`Context::destroy`] must be called
+    /// before the context is dropped.
+    pub unsafe fn create(window: &WinitWindow, config: &RendererConfig) -> Result<Self> {
+        let loader = LibloadingLoader::new(LIBRARY)?;
+        let entry = Entry::new(loader).map_err(|b| anyhow!("{}", b))?;
+        let mut data = ContextData {
+            debug: config.debug,
+            present_mode_preference: config.present_mode,
+            requested_msaa_samples: config.msaa_samples,
+            render_scale: config
+                .render_scale
+                .clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE),
+            ..Default::default()
+        };
+        let instance = create_instance(window, &entry, &mut data)?;
+        data.surface = vk_window::create_surface(&instance, &window, &window)?;
+        pick_physical_device(&instance, &mut data, &config.device_selector)?;
+        let device = create_logical_device(&entry, &instance, &mut data)?;
+        create_swapchain(window, &instance, &device, &mut data)?;
+        select_msaa_samples(&instance, &mut data);
+        create_render_pass(&instance, &device, &mut data)?;
+        create_pipeline(&device, &mut data)?;
+        create_command_pool(&instance, &device, &mut data)?;
+        create_render_targets(&instance, &device, &mut data)?;
+        data.vertex_buffer = VertexBuffer::create(&instance, &device, &data, TRIANGLE_VERTICES)?;
+        data.index_buffer = IndexBuffer::create(&instance, &device, &data, TRIANGLE_INDICES)?;
+        data.frames =
+            FramesInFlight::create(&instance, &device, &mut data, config.frames_in_flight)?;
+        data.images_in_flight = vec![vk::Fence::null(); data.swapchain_images.len()];
+        Ok(Self {
+            entry,
+            instance,
+            device,
+            data,
+        })
+    }
+
+    /// Recreates the swapchain, such as after the window has been resized, and the render
+    /// targets if the render resolution changed with it.
+    ///
+    /// # Safety
+    ///
+    /// The window must be the one the context was created for.
+    pub unsafe fn recreate_swapchain(&mut self, window: &WinitWindow) -> Result<()> {
+        self.device.device_wait_idle()?;
+        self.device.destroy_swapchain_khr(self.data.swapchain, None);
+        create_swapchain(window, &self.instance, &self.device, &mut self.data)?;
+        self.data
+            .images_in_flight
+            .resize(self.data.swapchain_images.len(), vk::Fence::null());
+        self.update_render_targets()
+    }
+
+    /// Recreates the render targets if the render resolution or MSAA sample count changed, along
+    /// with the render pass and pipeline if the sample count changed.
+    ///
+    /// # Safety
+    ///
+    /// The context must not have been destroyed.
+    pub unsafe fn update_render_targets(&mut self) -> Result<()> {
+        let samples = self.data.msaa_samples;
+        select_msaa_samples(&self.instance, &mut self.data);
+        let samples_changed = samples != self.data.msaa_samples;
+
+        if !samples_changed
+            && get_render_extent(&self.instance, &self.data) == self.data.render_extent
+        {
+            return Ok(());
+        }
+
+        self.device.device_wait_idle()?;
+        self.destroy_render_targets();
+
+        if samples_changed {
+            self.device.destroy_pipeline(self.data.pipeline, None);
+            self.device.destroy_render_pass(self.data.render_pass, None);
+            create_render_pass(&self.instance, &self.device, &mut self.data)?;
+            create_pipeline(&self.device, &mut self.data)?;
+        }
+
+        create_render_targets(&self.instance, &self.device, &mut self.data)
+    }
+
+    /// Destroys every Vulkan object owned by the context.
+    ///
+    /// # Safety
+    ///
+    /// The context must not be used again after it has been destroyed.
+    #[rustfmt::skip]
+    pub unsafe fn destroy(&mut self) {
+        self.device.device_wait_idle().unwrap();
+
+        self.destroy_render_targets();
+        self.device.destroy_pipeline(self.data.pipeline, None);
+        self.device.destroy_render_pass(self.data.render_pass, None);
+        self.device.destroy_swapchain_khr(self.data.swapchain, None);
+
+        self.data.frames.destroy(&self.device, self.data.command_pool);
+        self.data.index_buffer.destroy(&self.device);
+        self.data.vertex_buffer.destroy(&self.device);
+        self.data.descriptors.destroy(&self.device);
+        self.data.samplers.destroy(&self.device);
+        self.data.layouts.destroy(&self.device);
+        self.device.destroy_command_pool(self.data.command_pool, None);
+        self.device.destroy_device(None);
+        self.instance.destroy_surface_khr(self.data.surface, None);
+
+        if self.data.debug.enabled {
+            self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
+        }
+
+        self.instance.destroy_instance(None);
+    }
+
+    /// Destroys the render target and the attachments rendered along with it.
+    #[rustfmt::skip]
+    unsafe fn destroy_render_targets(&mut self) {
+        self.device.destroy_framebuffer(self.data.framebuffer, None);
+        destroy_color_objects(&self.device, &mut self.data);
+        self.device.destroy_image_view(self.data.depth_image_view, None);
+        self.device.free_memory(self.data.depth_image_memory, None);
+        self.device.destroy_image(self.data.depth_image, None);
+        self.device.destroy_image_view(self.data.target_image_view, None);
+        self.device.free_memory(self.data.target_image_memory, None);
+        self.device.destroy_image(self.data.target_image, None);
+    }
+}
+
+/// Creates the render target at the render resolution along with the attachments rendered with
+/// it and their framebuffer.
+unsafe fn create_render_targets(
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+) -> Result<()> {
+    data.render_extent = get_render_extent(instance, data);
+    create_target_objects(instance, device, data)?;
+    create_color_objects(instance, device, data)?;
+    create_depth_objects(instance, device, data)?;
+    create_framebuffer(device, data)
+}
+
+/// The Vulkan handles and associated properties owned by a [`Context`].
+#[derive(Clone, Debug, Default)]
+pub struct ContextData {
+    // Debug
+    pub debug: DebugConfig,
+    pub messenger: vk::DebugUtilsMessengerEXT,
+    // Surface
+    pub surface: vk::SurfaceKHR,
+    // Physical Device / Logical Device
+    pub physical_device: vk::PhysicalDevice,
+    pub graphics_queue: vk::Queue,
+    pub present_queue: vk::Queue,
+    // Swapchain
+    pub present_mode_preference: PresentModePreference,
+    pub swapchain_present_mode: vk::PresentModeKHR,
+    pub swapchain_format: vk::Format,
+    pub swapchain_extent: vk::Extent2D,
+    pub swapchain: vk::SwapchainKHR,
+    pub swapchain_images: Vec<vk::Image>,
+    // Render Target
+    pub render_scale: f32,
+    pub render_extent: vk::Extent2D,
+    pub target_image: vk::Image,
+    pub target_image_memory: vk::DeviceMemory,
+    pub target_image_view: vk::ImageView,
+    // Descriptors
+    pub layouts: LayoutCache,
+    pub samplers: SamplerCache,
+    pub descriptors: DescriptorAllocator,
+    // Pipeline
+    pub render_pass: vk::RenderPass,
+    pub pipeline_layout: vk::PipelineLayout,
+    pub pipeline: vk::Pipeline,
+    // MSAA
+    pub requested_msaa_samples: u32,
+    pub msaa_samples: vk::SampleCountFlags,
+    pub color_image: vk::Image,
+    pub color_image_memory: vk::DeviceMemory,
+    pub color_image_view: vk::ImageView,
+    // Depth
+    pub depth_image: vk::Image,
+    pub depth_image_memory: vk::DeviceMemory,
+    pub depth_image_view: vk::ImageView,
+    // Framebuffer
+    pub framebuffer: vk::Framebuffer,
+    // Command Pool
+    pub command_pool: vk::CommandPool,
+    // Geometry
+    pub vertex_buffer: VertexBuffer<ColorVertex>,
+    pub index_buffer: IndexBuffer,
+    // Frames In Flight
+    pub frames: FramesInFlight,
+    pub images_in_flight: Vec<vk::Fence>,
+}
@@ -0,0 +1,120 @@
+//! Multisample anti-aliasing: sample count selection and the multisampled color target.
+
+use anyhow::Result;
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{
+    ContextData,
+    image::{create_image, create_image_view},
+    target::RENDER_TARGET_FORMAT,
+};
+
+/// Every sample count in increasing order.
+const SAMPLE_COUNTS: &[vk::SampleCountFlags] = &[
+    vk::SampleCountFlags::_1,
+    vk::SampleCountFlags::_2,
+    vk::SampleCountFlags::_4,
+    vk::SampleCountFlags::_8,
+    vk::SampleCountFlags::_16,
+    vk::SampleCountFlags::_32,
+    vk::SampleCountFlags::_64,
+];
+
+/// Returns whether multisampling is enabled, in which case frames are rendered into a
+/// multisampled color image and resolved into the render target.
+pub(crate) fn is_msaa_enabled(data: &ContextData) -> bool {
+    data.msaa_samples != vk::SampleCountFlags::_1
+}
+
+/// Returns the largest sample count supported by both color and depth framebuffer attachments.
+pub(crate) unsafe fn get_max_msaa_samples(
+    instance: &Instance,
+    data: &ContextData,
+) -> vk::SampleCountFlags {
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    let counts = properties.limits.framebuffer_color_sample_counts
+        & properties.limits.framebuffer_depth_sample_counts;
+    SAMPLE_COUNTS
+        .iter()
+        .cloned()
+        .rev()
+        .find(|c| counts.contains(*c))
+        .unwrap_or(vk::SampleCountFlags::_1)
+}
+
+/// Picks the largest supported sample count that does not exceed the requested sample count.
+pub(crate) unsafe fn select_msaa_samples(instance: &Instance, data: &mut ContextData) {
+    let max = get_max_msaa_samples(instance, data);
+    let samples = SAMPLE_COUNTS
+        .iter()
+        .cloned()
+        .rev()
+        .find(|c| *c <= max && c.bits() <= data.requested_msaa_samples.max(1))
+        .unwrap_or(vk::SampleCountFlags::_1);
+
+    if samples != data.msaa_samples && samples.bits() != data.requested_msaa_samples {
+        warn!(
+            "Requested {}x MSAA, using {}x (device maximum is {}x).",
+            data.requested_msaa_samples,
+            samples.bits(),
+            max.bits()
+        );
+    }
+
+    data.msaa_samples = samples;
+}
+
+/// Creates the multisampled color image that is resolved into the render target.
+pub(crate) unsafe fn create_color_objects(
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+) -> Result<()> {
+    if !is_msaa_enabled(data) {
+        return Ok(());
+    }
+
+    // Image + Image Memory
+
+    let (color_image, color_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.render_extent.width,
+        data.render_extent.height,
+        1,
+        data.msaa_samples,
+        RENDER_TARGET_FORMAT,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.color_image = color_image;
+    data.color_image_memory = color_image_memory;
+
+    // Image View
+
+    data.color_image_view = create_image_view(
+        device,
+        data.color_image,
+        RENDER_TARGET_FORMAT,
+        vk::ImageAspectFlags::COLOR,
+        1,
+    )?;
+
+    Ok(())
+}
+
+/// Destroys the multisampled color image, if there is one.
+pub(crate) unsafe fn destroy_color_objects(device: &Device, data: &mut ContextData) {
+    if !data.color_image.is_null() {
+        device.destroy_image_view(data.color_image_view, None);
+        device.free_memory(data.color_image_memory, None);
+        device.destroy_image(data.color_image, None);
+        data.color_image = vk::Image::null();
+        data.color_image_memory = vk::DeviceMemory::null();
+        data.color_image_view = vk::ImageView::null();
+    }
+}
@@ -0,0 +1,71 @@
+//! Typed push constants.
+
+use std::mem::size_of;
+
+use glam::Mat4;
+use vulkanalia::prelude::v1_0::*;
+
+/// A `#[repr(C)]` struct pushed to the shader stages of a pipeline without a descriptor set.
+///
+/// The range is registered on the pipeline layout when passed to
+/// [`LayoutCache::reflect_pipeline_layout`](super::LayoutCache::reflect_pipeline_layout), and
+/// values are pushed while recording with [`cmd_push_constants`].
+///
+/// Vulkan only guarantees 128 bytes of push constants, which fits two 4x4 matrices.
+///
+/// # Safety
+///
+/// The struct must be `#[repr(C)]` without padding bytes, since values are pushed as raw bytes
+/// and reading padding is undefined behavior.
+pub unsafe trait PushConstants: Copy + 'static {
+    /// The shader stages that read the push constants.
+    const STAGES: vk::ShaderStageFlags;
+
+    /// Returns the push constant range of the struct.
+    fn range() -> vk::PushConstantRange {
+        vk::PushConstantRange::builder()
+            .stage_flags(Self::STAGES)
+            .offset(0)
+            .size(size_of::<Self>() as u32)
+            .build()
+    }
+}
+
+/// Pushes a value to the stages of its push constant range.
+///
+/// # Safety
+///
+/// The command buffer must be recording, and the layout must have been created with the range
+/// of `P`. `P` must be `#[repr(C)]` without padding bytes, as required by [`PushConstants`].
+pub unsafe fn cmd_push_constants<P: PushConstants>(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    layout: vk::PipelineLayout,
+    value: &P,
+) {
+    let bytes = std::slice::from_raw_parts((value as *const P).cast::<u8>(), size_of::<P>());
+    device.cmd_push_constants(command_buffer, layout, P::STAGES, 0, bytes);
+}
+
+/// The per-draw transform of an object, pushed to the vertex stage.
+///
+/// Matches the `ObjectConstants` push constant block in `shaders/triangle.vert.glsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ObjectConstants {
+    /// Transforms object space into world space.
+    pub model: Mat4,
+}
+
+impl Default for ObjectConstants {
+    fn default() -> Self {
+        Self {
+            model: Mat4::IDENTITY,
+        }
+    }
+}
+
+// SAFETY: A single `Mat4` contains no padding bytes.
+unsafe impl PushConstants for ObjectConstants {
+    const STAGES: vk::ShaderStageFlags = vk::ShaderStageFlags::VERTEX;
+}
@@ -0,0 +1,147 @@
+//! Samplers shared between textures.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+/// Describes how a texture is filtered and addressed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SamplerDesc {
+    pub mag_filter: vk::Filter,
+    pub min_filter: vk::Filter,
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    pub address_mode: vk::SamplerAddressMode,
+    /// The maximum anisotropy, where 1 disables anisotropic filtering. Clamped to the device
+    /// limit.
+    pub max_anisotropy: u32,
+}
+
+impl SamplerDesc {
+    /// Linear filtering between texels and mip levels.
+    pub fn linear() -> Self {
+        Self::default()
+    }
+
+    /// Nearest filtering, for pixel art and lookup textures.
+    pub fn nearest() -> Self {
+        Self {
+            mag_filter: vk::Filter::NEAREST,
+            min_filter: vk::Filter::NEAREST,
+            mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the address mode used for every texture coordinate.
+    pub fn with_address_mode(self, address_mode: vk::SamplerAddressMode) -> Self {
+        Self {
+            address_mode,
+            ..self
+        }
+    }
+
+    /// Sets the maximum anisotropy, where 1 disables anisotropic filtering.
+    pub fn with_anisotropy(self, max_anisotropy: u32) -> Self {
+        Self {
+            max_anisotropy,
+            ..self
+        }
+    }
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode: vk::SamplerAddressMode::REPEAT,
+            max_anisotropy: 1,
+        }
+    }
+}
+
+/// Creates one sampler per [`SamplerDesc`] and hands out the same sampler for repeated requests.
+///
+/// Samplers cover every mip level, so textures with different mip counts can share them.
+#[derive(Clone, Debug, Default)]
+pub struct SamplerCache {
+    max_anisotropy: f32,
+    samplers: HashMap<SamplerDesc, vk::Sampler>,
+}
+
+impl SamplerCache {
+    /// Creates an empty cache for a device that supports anisotropy up to `max_anisotropy`, where
+    /// 1 means anisotropic filtering is unsupported.
+    pub(crate) fn new(max_anisotropy: f32) -> Self {
+        Self {
+            max_anisotropy,
+            samplers: HashMap::new(),
+        }
+    }
+
+    /// Returns the sampler for a description, creating it if needed.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every cached sampler was created with.
+    pub unsafe fn get(&mut self, device: &Device, desc: SamplerDesc) -> Result<vk::Sampler> {
+        if let Some(sampler) = self.samplers.get(&desc) {
+            return Ok(*sampler);
+        }
+
+        let max_anisotropy = (desc.max_anisotropy as f32).min(self.max_anisotropy);
+        if max_anisotropy < desc.max_anisotropy as f32 {
+            debug!(
+                "Clamping sampler anisotropy from {} to {max_anisotropy}.",
+                desc.max_anisotropy
+            );
+        }
+
+        let info = vk::SamplerCreateInfo::builder()
+            .mag_filter(desc.mag_filter)
+            .min_filter(desc.min_filter)
+            .address_mode_u(desc.address_mode)
+            .address_mode_v(desc.address_mode)
+            .address_mode_w(desc.address_mode)
+            .anisotropy_enable(max_anisotropy > 1.0)
+            .max_anisotropy(max_anisotropy.max(1.0))
+            .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vk::CompareOp::ALWAYS)
+            .mipmap_mode(desc.mipmap_mode)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(vk::LOD_CLAMP_NONE);
+
+        let sampler = device.create_sampler(&info, None)?;
+
+        self.samplers.insert(desc, sampler);
+        Ok(sampler)
+    }
+
+    /// Returns the number of cached samplers.
+    pub fn len(&self) -> usize {
+        self.samplers.len()
+    }
+
+    /// Returns whether no samplers have been created yet.
+    pub fn is_empty(&self) -> bool {
+        self.samplers.is_empty()
+    }
+
+    /// Destroys every cached sampler.
+    ///
+    /// # Safety
+    ///
+    /// The device must be the one every cached sampler was created with, and the samplers must
+    /// no longer be in use.
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for (_, sampler) in self.samplers.drain() {
+            device.destroy_sampler(sampler, None);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Swapchain creation and the surface properties it is built from.
+
+use anyhow::{Result, anyhow};
+use log::*;
+use vulkanalia::{
+    prelude::v1_0::*,
+    vk::{KhrSurfaceExtension, KhrSwapchainExtension},
+};
+use winit::window::Window as WinitWindow;
+
+use super::{ContextData, device::QueueFamilyIndices};
+
+/// The preferred way for the swapchain to present images, falling back to FIFO (which every
+/// device supports) when the preferred mode is not available.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PresentModePreference {
+    /// Waits for vertical blank and never tears (VSync on).
+    Fifo,
+    /// Replaces the queued image with the newest one, rendering uncapped without tearing.
+    #[default]
+    Mailbox,
+    /// Presents immediately and may tear (VSync off). Falls back to mailbox before FIFO.
+    Immediate,
+    /// Like FIFO, but presents immediately when a frame misses vertical blank.
+    FifoRelaxed,
+}
+
+impl PresentModePreference {
+    /// Returns the present modes to try in order of preference.
+    fn candidates(self) -> &'static [vk::PresentModeKHR] {
+        match self {
+            Self::Fifo => &[vk::PresentModeKHR::FIFO],
+            Self::Mailbox => &[vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO],
+            Self::Immediate => &[
+                vk::PresentModeKHR::IMMEDIATE,
+                vk::PresentModeKHR::MAILBOX,
+                vk::PresentModeKHR::FIFO,
+            ],
+            Self::FifoRelaxed => &[vk::PresentModeKHR::FIFO_RELAXED, vk::PresentModeKHR::FIFO],
+        }
+    }
+}
+
+pub(crate) unsafe fn create_swapchain(
+    window: &WinitWindow,
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+) -> Result<()> {
+    // Image
+
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+    let support = SwapchainSupport::get(instance, data, data.physical_device)?;
+
+    let surface_format = get_swapchain_surface_format(&support.formats);
+    let present_mode =
+        get_swapchain_present_mode(&support.present_modes, data.present_mode_preference);
+    let extent = get_swapchain_extent(window, support.capabilities);
+
+    // Frames are blitted from the render target into the swapchain images.
+    let properties =
+        instance.get_physical_device_format_properties(data.physical_device, surface_format.format);
+    if !support
+        .capabilities
+        .supported_usage_flags
+        .contains(vk::ImageUsageFlags::TRANSFER_DST)
+        || !properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::BLIT_DST)
+    {
+        return Err(anyhow!(
+            "Swapchain format {:?} does not support blitting into.",
+            surface_format.format
+        ));
+    }
+
+    if data.swapchain.is_null() || present_mode != data.swapchain_present_mode {
+        info!(
+            "Using present mode {present_mode:?} (preferred {:?}).",
+            data.present_mode_preference
+        );
+    }
+
+    data.swapchain_format = surface_format.format;
+    data.swapchain_extent = extent;
+    data.swapchain_present_mode = present_mode;
+
+    let mut image_count = support.capabilities.min_image_count + 1;
+    if support.capabilities.max_image_count != 0
+        && image_count > support.capabilities.max_image_count
+    {
+        image_count = support.capabilities.max_image_count;
+    }
+
+    let mut queue_family_indices = vec![];
+    let image_sharing_mode = if indices.graphics != indices.present {
+        queue_family_indices.push(indices.graphics);
+        queue_family_indices.push(indices.present);
+        vk::SharingMode::CONCURRENT
+    } else {
+        vk::SharingMode::EXCLUSIVE
+    };
+
+    // Create
+
+    let info = vk::SwapchainCreateInfoKHR::builder()
+        .surface(data.surface)
+        .min_image_count(image_count)
+        .image_format(surface_format.format)
+        .image_color_space(surface_format.color_space)
+        .image_extent(extent)
+        .image_array_layers(1)
+        .image_usage(vk::ImageUsageFlags::TRANSFER_DST)
+        .image_sharing_mode(image_sharing_mode)
+        .queue_family_indices(&queue_family_indices)
+        .pre_transform(support.capabilities.current_transform)
+        .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+        .present_mode(present_mode)
+        .clipped(true)
+        .old_swapchain(vk::SwapchainKHR::null());
+
+    data.swapchain = device.create_swapchain_khr(&info, None)?;
+
+    // Images
+
+    data.swapchain_images = device.get_swapchain_images_khr(data.swapchain)?;
+
+    Ok(())
+}
+
+fn get_swapchain_surface_format(formats: &[vk::SurfaceFormatKHR]) -> vk::SurfaceFormatKHR {
+    formats
+        .iter()
+        .cloned()
+        .find(|f| {
+            f.format == vk::Format::B8G8R8A8_SRGB
+                && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+        })
+        .unwrap_or_else(|| formats[0])
+}
+
+fn get_swapchain_present_mode(
+    present_modes: &[vk::PresentModeKHR],
+    preference: PresentModePreference,
+) -> vk::PresentModeKHR {
+    preference
+        .candidates()
+        .iter()
+        .cloned()
+        .find(|m| present_modes.contains(m))
+        .unwrap_or(vk::PresentModeKHR::FIFO)
+}
+
+#[rustfmt::skip]
+fn get_swapchain_extent(window: &WinitWindow, capabilities: vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
+    if capabilities.current_extent.width != u32::MAX {
+        capabilities.current_extent
+    } else {
+        vk::Extent2D::builder()
+            .width(window.inner_size().width.clamp(
+                capabilities.min_image_extent.width,
+                capabilities.max_image_extent.width,
+            ))
+            .height(window.inner_size().height.clamp(
+                capabilities.min_image_extent.height,
+                capabilities.max_image_extent.height,
+            ))
+            .build()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SwapchainSupport {
+    pub capabilities: vk::SurfaceCapabilitiesKHR,
+    pub formats: Vec<vk::SurfaceFormatKHR>,
+    pub present_modes: Vec<vk::PresentModeKHR>,
+}
+
+impl SwapchainSupport {
+    pub unsafe fn get(
+        instance: &Instance,
+        data: &ContextData,
+        physical_device: vk::PhysicalDevice,
+    ) -> Result<Self> {
+        Ok(Self {
+            capabilities: instance
+                .get_physical_device_surface_capabilities_khr(physical_device, data.surface)?,
+            formats: instance
+                .get_physical_device_surface_formats_khr(physical_device, data.surface)?,
+            present_modes: instance
+                .get_physical_device_surface_present_modes_khr(physical_device, data.surface)?,
+        })
+    }
+}
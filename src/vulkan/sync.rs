@@ -0,0 +1,52 @@
+//! Fence waits guarded by a GPU watchdog.
+
+use std::{env, time::Duration};
+
+use anyhow::{Result, anyhow};
+use log::*;
+use thiserror::Error;
+use vulkanalia::prelude::v1_0::*;
+
+/// How long to wait for a fence before the GPU is considered hung.
+pub const DEFAULT_GPU_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+#[error("GPU did not finish `{checkpoint}` within {timeout:?}.")]
+pub struct GpuHangError {
+    pub timeout: Duration,
+    pub checkpoint: String,
+}
+
+/// Returns the GPU watchdog timeout, which can be overridden in milliseconds with the
+/// `VULKANRS_GPU_TIMEOUT_MS` environment variable.
+pub(crate) fn get_gpu_timeout() -> Duration {
+    match env::var("VULKANRS_GPU_TIMEOUT_MS").map(|v| v.parse::<u64>()) {
+        Ok(Ok(millis)) => Duration::from_millis(millis),
+        Ok(Err(error)) => {
+            warn!("Ignoring invalid `VULKANRS_GPU_TIMEOUT_MS`: {error}");
+            DEFAULT_GPU_TIMEOUT
+        }
+        Err(_) => DEFAULT_GPU_TIMEOUT,
+    }
+}
+
+/// Waits for a fence, reporting a GPU hang if it does not signal within the timeout.
+pub(crate) unsafe fn wait_for_fence(
+    device: &Device,
+    fence: vk::Fence,
+    timeout: Duration,
+    checkpoint: &str,
+) -> Result<()> {
+    let nanos = timeout.as_nanos().min(u64::MAX as u128) as u64;
+
+    if device.wait_for_fences(&[fence], true, nanos)? == vk::SuccessCode::TIMEOUT {
+        error!("GPU watchdog: fence did not signal within {timeout:?}.");
+        error!("GPU watchdog: last submitted work was `{checkpoint}`.");
+        return Err(anyhow!(GpuHangError {
+            timeout,
+            checkpoint: checkpoint.to_string(),
+        }));
+    }
+
+    Ok(())
+}
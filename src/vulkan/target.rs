@@ -0,0 +1,180 @@
+//! The offscreen render target that frames are drawn into at the render resolution and blitted
+//! to the swapchain image at the end of the frame.
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{
+    ContextData,
+    image::{create_image, create_image_view},
+};
+
+/// The smallest render scale.
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+
+/// The largest render scale, which renders at 4x4 the swapchain resolution.
+pub const MAX_RENDER_SCALE: f32 = 4.0;
+
+/// The format of the render target. Every device supports it as a color attachment and blit
+/// source, independent of the swapchain format.
+pub(crate) const RENDER_TARGET_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+/// Returns the render resolution, which is the swapchain extent multiplied by the render scale
+/// and limited by the largest framebuffer the device supports.
+pub(crate) unsafe fn get_render_extent(instance: &Instance, data: &ContextData) -> vk::Extent2D {
+    let limits = instance
+        .get_physical_device_properties(data.physical_device)
+        .limits;
+
+    let scale = |size: u32, max: u32| {
+        ((size as f32 * data.render_scale).round() as u32)
+            .clamp(1, max.min(limits.max_image_dimension_2d))
+    };
+
+    vk::Extent2D {
+        width: scale(data.swapchain_extent.width, limits.max_framebuffer_width),
+        height: scale(data.swapchain_extent.height, limits.max_framebuffer_height),
+    }
+}
+
+/// Creates the single-sampled color image the frame ends up in, resolved into from the
+/// multisampled color image when MSAA is enabled.
+pub(crate) unsafe fn create_target_objects(
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+) -> Result<()> {
+    // Image + Image Memory
+
+    let (target_image, target_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.render_extent.width,
+        data.render_extent.height,
+        1,
+        vk::SampleCountFlags::_1,
+        RENDER_TARGET_FORMAT,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.target_image = target_image;
+    data.target_image_memory = target_image_memory;
+
+    // Image View
+
+    data.target_image_view = create_image_view(
+        device,
+        data.target_image,
+        RENDER_TARGET_FORMAT,
+        vk::ImageAspectFlags::COLOR,
+        1,
+    )?;
+
+    Ok(())
+}
+
+/// Records the commands that copy the render target into a swapchain image, scaling it to the
+/// swapchain extent, and prepare the swapchain image for presentation.
+///
+/// The render target must be in the `TRANSFER_SRC_OPTIMAL` layout the render pass leaves it in.
+pub(crate) unsafe fn cmd_blit_to_swapchain(
+    device: &Device,
+    data: &ContextData,
+    command_buffer: vk::CommandBuffer,
+    image_index: usize,
+) {
+    let image = data.swapchain_images[image_index];
+
+    let subresource_range = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    // Transition To Transfer
+
+    // The previous contents are discarded. The source stage matches the stage that waits for the
+    // image to be acquired.
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(vk::ImageLayout::UNDEFINED)
+        .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range)
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+
+    // Blit
+
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let corner = |extent: vk::Extent2D| vk::Offset3D {
+        x: extent.width as i32,
+        y: extent.height as i32,
+        z: 1,
+    };
+
+    let blit = vk::ImageBlit::builder()
+        .src_offsets([vk::Offset3D::default(), corner(data.render_extent)])
+        .src_subresource(subresource)
+        .dst_offsets([vk::Offset3D::default(), corner(data.swapchain_extent)])
+        .dst_subresource(subresource);
+
+    // Copy pixels exactly when no scaling is needed.
+    let filter = if data.render_extent == data.swapchain_extent {
+        vk::Filter::NEAREST
+    } else {
+        vk::Filter::LINEAR
+    };
+
+    device.cmd_blit_image(
+        command_buffer,
+        data.target_image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        image,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        &[blit],
+        filter,
+    );
+
+    // Transition To Present
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+        .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range)
+        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+        .dst_access_mask(vk::AccessFlags::empty());
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+}
@@ -0,0 +1,651 @@
+//! Sampled 2D textures loaded from image files.
+
+use std::{path::Path, ptr::copy_nonoverlapping as memcpy};
+
+use anyhow::{Result, anyhow};
+use image::{RgbaImage, imageops::FilterType};
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{
+    ContextData,
+    buffer::create_buffer,
+    command::{begin_single_time_commands, end_single_time_commands},
+    descriptor::DescriptorSetBuilder,
+    image::{create_image, create_image_view},
+    sampler::SamplerDesc,
+};
+
+/// Options used when creating a [`Texture2D`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextureOptions {
+    /// Whether to generate the full mip chain. Usually disabled for UI textures, which are drawn
+    /// at their native size.
+    pub mipmaps: bool,
+    /// Whether the pixels are sRGB encoded colors, as opposed to linear data such as normals.
+    pub srgb: bool,
+    /// How the texture is sampled. Textures with the same description share a sampler.
+    pub sampler: SamplerDesc,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            mipmaps: true,
+            srgb: true,
+            sampler: SamplerDesc::default(),
+        }
+    }
+}
+
+/// Tightly packed 8-bit RGBA pixels to create a [`Texture2D`] from.
+#[derive(Copy, Clone, Debug)]
+pub struct TextureSource<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: &'a [u8],
+    pub options: TextureOptions,
+}
+
+/// A device local 2D image with a view, a sampler and a descriptor set for the
+/// [`MATERIAL_SET`](super::descriptor::MATERIAL_SET) slot.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Texture2D {
+    pub width: u32,
+    pub height: u32,
+    pub mip_levels: u32,
+    pub image: vk::Image,
+    pub image_memory: vk::DeviceMemory,
+    pub image_view: vk::ImageView,
+    pub sampler: vk::Sampler,
+    pub descriptor_set: vk::DescriptorSet,
+}
+
+impl Texture2D {
+    /// Loads a PNG or JPEG file into a texture.
+    ///
+    /// # Safety
+    ///
+    /// The context data must belong to the device, and [`Texture2D::destroy`] must be called
+    /// before the device is destroyed.
+    pub unsafe fn load(
+        instance: &Instance,
+        device: &Device,
+        data: &mut ContextData,
+        path: impl AsRef<Path>,
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|e| anyhow!("Failed to load texture `{}`: {e}", path.display()))?
+            .to_rgba8();
+
+        Self::from_rgba8(
+            instance,
+            device,
+            data,
+            image.width(),
+            image.height(),
+            &image,
+            options,
+        )
+    }
+
+    /// Creates a texture from tightly packed 8-bit RGBA pixels.
+    ///
+    /// # Safety
+    ///
+    /// See [`Texture2D::load`].
+    pub unsafe fn from_rgba8(
+        instance: &Instance,
+        device: &Device,
+        data: &mut ContextData,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        let source = TextureSource {
+            width,
+            height,
+            pixels,
+            options: *options,
+        };
+
+        Ok(Self::from_rgba8_batch(instance, device, data, &[source])?[0])
+    }
+
+    /// Creates a texture for each of `sources` and uploads them all in a single submission.
+    ///
+    /// Nothing is leaked if any of the textures cannot be created.
+    ///
+    /// # Safety
+    ///
+    /// See [`Texture2D::load`].
+    pub unsafe fn from_rgba8_batch(
+        instance: &Instance,
+        device: &Device,
+        data: &mut ContextData,
+        sources: &[TextureSource],
+    ) -> Result<Vec<Self>> {
+        if sources.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let command_buffer = begin_single_time_commands(device, data)?;
+
+        // Record
+
+        let mut uploads = Vec::with_capacity(sources.len());
+        let mut result = Ok(());
+
+        for source in sources {
+            uploads.push(Upload::default());
+            let upload = uploads.last_mut().unwrap();
+
+            result = record_upload(instance, device, data, command_buffer, source, upload);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // Submit
+
+        result = match result {
+            Ok(()) => end_single_time_commands(device, data, command_buffer),
+            Err(error) => {
+                device.free_command_buffers(data.command_pool, &[command_buffer]);
+                Err(error)
+            }
+        };
+
+        // Cleanup
+
+        for upload in &uploads {
+            device.destroy_buffer(upload.staging_buffer, None);
+            device.free_memory(upload.staging_buffer_memory, None);
+        }
+
+        let mut textures = uploads.into_iter().map(|u| u.texture).collect::<Vec<_>>();
+
+        if let Err(error) = result {
+            textures.iter_mut().for_each(|t| t.destroy(device, data));
+            return Err(error);
+        }
+
+        Ok(textures)
+    }
+
+    /// Destroys the texture and frees its descriptor set. The sampler is owned by the
+    /// [`SamplerCache`](super::SamplerCache) and outlives the texture.
+    ///
+    /// # Safety
+    ///
+    /// The texture must no longer be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &Device, data: &mut ContextData) {
+        // The image is still destroyed if the set cannot be freed, since the pool is destroyed
+        // with the context anyway. The set is null if the texture failed to be created.
+        if !self.descriptor_set.is_null()
+            && let Err(error) = data.descriptors.free(device, self.descriptor_set)
+        {
+            warn!("Failed to free the texture descriptor set: {error:#}");
+        }
+
+        device.destroy_image_view(self.image_view, None);
+        device.free_memory(self.image_memory, None);
+        device.destroy_image(self.image, None);
+    }
+}
+
+//================================================
+// Upload
+//================================================
+
+/// A texture whose upload has been recorded, and the staging buffer it is copied from. Handles
+/// stay null until they are created, so a partially recorded upload can be destroyed.
+#[derive(Default)]
+struct Upload {
+    texture: Texture2D,
+    staging_buffer: vk::Buffer,
+    staging_buffer_memory: vk::DeviceMemory,
+}
+
+/// Creates the image, view and descriptor set of a texture and records the commands that fill it
+/// from a new staging buffer.
+unsafe fn record_upload(
+    instance: &Instance,
+    device: &Device,
+    data: &mut ContextData,
+    command_buffer: vk::CommandBuffer,
+    source: &TextureSource,
+    upload: &mut Upload,
+) -> Result<()> {
+    let TextureSource {
+        width,
+        height,
+        pixels,
+        options,
+    } = *source;
+
+    let size = width as u64 * height as u64 * 4;
+    if size == 0 {
+        return Err(anyhow!("Cannot create an empty {width}x{height} texture."));
+    } else if pixels.len() as u64 != size {
+        return Err(anyhow!(
+            "Expected {size} bytes of pixels for a {width}x{height} texture, got {}.",
+            pixels.len()
+        ));
+    }
+
+    // Mip Levels
+
+    let format = if options.srgb {
+        vk::Format::R8G8B8A8_SRGB
+    } else {
+        vk::Format::R8G8B8A8_UNORM
+    };
+    let mip_levels = if options.mipmaps {
+        u32::BITS - width.max(height).leading_zeros()
+    } else {
+        1
+    };
+
+    // Mip levels are blitted on the GPU when the format supports linear blits, otherwise they are
+    // downsampled on the CPU and uploaded along with the base level.
+    let blit = mip_levels > 1 && supports_linear_blit(instance, data, format);
+    let levels = if mip_levels > 1 && !blit {
+        debug!("Linear blits unsupported for {format:?}, generating mipmaps on the CPU.");
+        let image = RgbaImage::from_raw(width, height, pixels.to_vec())
+            .ok_or_else(|| anyhow!("Invalid texture pixels."))?;
+        downsample_mip_levels(image, mip_levels)
+    } else {
+        vec![pixels.to_vec()]
+    };
+
+    // Staging Buffer
+
+    let size = levels.iter().map(|l| l.len() as u64).sum::<u64>();
+    (upload.staging_buffer, upload.staging_buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    let memory = device.map_memory(
+        upload.staging_buffer_memory,
+        0,
+        size,
+        vk::MemoryMapFlags::empty(),
+    )?;
+
+    let mut offset = 0;
+    for level in &levels {
+        memcpy(level.as_ptr(), memory.cast::<u8>().add(offset), level.len());
+        offset += level.len();
+    }
+
+    device.unmap_memory(upload.staging_buffer_memory);
+
+    // Image
+
+    let texture = &mut upload.texture;
+    texture.width = width;
+    texture.height = height;
+    texture.mip_levels = mip_levels;
+
+    (texture.image, texture.image_memory) = create_image(
+        instance,
+        device,
+        data,
+        width,
+        height,
+        mip_levels,
+        vk::SampleCountFlags::_1,
+        format,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::SAMPLED
+            | vk::ImageUsageFlags::TRANSFER_DST
+            | vk::ImageUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    // Upload
+
+    cmd_transition_image_layout(
+        device,
+        command_buffer,
+        texture.image,
+        mip_levels,
+        vk::ImageLayout::UNDEFINED,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+    )?;
+
+    cmd_copy_buffer_to_image(
+        device,
+        command_buffer,
+        upload.staging_buffer,
+        texture.image,
+        width,
+        height,
+        &levels,
+    );
+
+    if blit {
+        cmd_generate_mipmaps(
+            device,
+            command_buffer,
+            texture.image,
+            width,
+            height,
+            mip_levels,
+        );
+    } else {
+        cmd_transition_image_layout(
+            device,
+            command_buffer,
+            texture.image,
+            mip_levels,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        )?;
+    }
+
+    // Image View + Sampler
+
+    texture.image_view = create_image_view(
+        device,
+        texture.image,
+        format,
+        vk::ImageAspectFlags::COLOR,
+        mip_levels,
+    )?;
+    texture.sampler = data.samplers.get(device, options.sampler)?;
+
+    // Descriptor Set
+
+    texture.descriptor_set = DescriptorSetBuilder::new()
+        .combined_image_sampler(
+            0,
+            texture.image_view,
+            texture.sampler,
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .build(device, data)?;
+
+    Ok(())
+}
+
+/// Records a transition of every mip level of a color image between the layouts used for uploads.
+unsafe fn cmd_transition_image_layout(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    mip_levels: u32,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+) -> Result<()> {
+    let (src_access_mask, dst_access_mask, src_stage_mask, dst_stage_mask) =
+        match (old_layout, new_layout) {
+            (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => (
+                vk::AccessFlags::empty(),
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+            ),
+            (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::SHADER_READ,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+            ),
+            _ => return Err(anyhow!("Unsupported image layout transition!")),
+        };
+
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(mip_levels)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource)
+        .src_access_mask(src_access_mask)
+        .dst_access_mask(dst_access_mask);
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        src_stage_mask,
+        dst_stage_mask,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+
+    Ok(())
+}
+
+/// Records a copy of tightly packed mip levels, stored one after another in a buffer, into the
+/// first mip levels of an image.
+unsafe fn cmd_copy_buffer_to_image(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    buffer: vk::Buffer,
+    image: vk::Image,
+    width: u32,
+    height: u32,
+    levels: &[Vec<u8>],
+) {
+    let mut offset = 0;
+    let regions = levels
+        .iter()
+        .enumerate()
+        .map(|(i, level)| {
+            let subresource = vk::ImageSubresourceLayers::builder()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .mip_level(i as u32)
+                .base_array_layer(0)
+                .layer_count(1);
+
+            let region = vk::BufferImageCopy::builder()
+                .buffer_offset(offset)
+                .buffer_row_length(0)
+                .buffer_image_height(0)
+                .image_subresource(subresource)
+                .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                .image_extent(vk::Extent3D {
+                    width: (width >> i).max(1),
+                    height: (height >> i).max(1),
+                    depth: 1,
+                })
+                .build();
+
+            offset += level.len() as u64;
+            region
+        })
+        .collect::<Vec<_>>();
+
+    device.cmd_copy_buffer_to_image(
+        command_buffer,
+        buffer,
+        image,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        &regions,
+    );
+}
+
+//================================================
+// Mipmaps
+//================================================
+
+/// Returns whether images with the format can be linearly blitted from and to.
+unsafe fn supports_linear_blit(
+    instance: &Instance,
+    data: &ContextData,
+    format: vk::Format,
+) -> bool {
+    let properties = instance.get_physical_device_format_properties(data.physical_device, format);
+    properties.optimal_tiling_features.contains(
+        vk::FormatFeatureFlags::BLIT_SRC
+            | vk::FormatFeatureFlags::BLIT_DST
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+    )
+}
+
+/// Downsamples an image into a mip chain, starting with the image itself.
+fn downsample_mip_levels(image: RgbaImage, mip_levels: u32) -> Vec<Vec<u8>> {
+    let (width, height) = image.dimensions();
+    let mut levels = Vec::with_capacity(mip_levels as usize);
+    let mut previous = image;
+
+    for i in 1..mip_levels {
+        let level = image::imageops::resize(
+            &previous,
+            (width >> i).max(1),
+            (height >> i).max(1),
+            FilterType::Triangle,
+        );
+        levels.push(std::mem::replace(&mut previous, level).into_raw());
+    }
+
+    levels.push(previous.into_raw());
+    levels
+}
+
+/// Records commands that fill every mip level after the first by blitting from the previous
+/// level, leaving the whole image in `SHADER_READ_ONLY_OPTIMAL`.
+unsafe fn cmd_generate_mipmaps(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    width: u32,
+    height: u32,
+    mip_levels: u32,
+) {
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_array_layer(0)
+        .layer_count(1)
+        .level_count(1);
+
+    let mut barrier = vk::ImageMemoryBarrier::builder()
+        .image(image)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .subresource_range(subresource);
+
+    let mut mip_width = width as i32;
+    let mut mip_height = height as i32;
+
+    for i in 1..mip_levels {
+        // The previous level has been written and is now read by the blit.
+
+        barrier.subresource_range.base_mip_level = i - 1;
+        barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+        barrier.new_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+        barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
+        barrier.dst_access_mask = vk::AccessFlags::TRANSFER_READ;
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+
+        let src_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(i - 1)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let dst_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(i)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let next_width = (mip_width / 2).max(1);
+        let next_height = (mip_height / 2).max(1);
+
+        let blit = vk::ImageBlit::builder()
+            .src_offsets([
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: mip_width,
+                    y: mip_height,
+                    z: 1,
+                },
+            ])
+            .src_subresource(src_subresource)
+            .dst_offsets([
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: next_width,
+                    y: next_height,
+                    z: 1,
+                },
+            ])
+            .dst_subresource(dst_subresource);
+
+        device.cmd_blit_image(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[blit],
+            vk::Filter::LINEAR,
+        );
+
+        // The previous level is finished and can be sampled.
+
+        barrier.old_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+        barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+        barrier.src_access_mask = vk::AccessFlags::TRANSFER_READ;
+        barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+
+        mip_width = next_width;
+        mip_height = next_height;
+    }
+
+    // The last level is never blitted from.
+
+    barrier.subresource_range.base_mip_level = mip_levels - 1;
+    barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+    barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+    barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
+    barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::FRAGMENT_SHADER,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+}
@@ -0,0 +1,153 @@
+//! Vertex types and the vertex input layouts derived from them.
+
+use std::mem::size_of;
+
+use glam::{IVec2, IVec3, IVec4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+use vulkanalia::prelude::v1_0::*;
+
+/// A vertex type that can be stored in a [`VertexBuffer`](super::VertexBuffer).
+///
+/// Implement it with [`impl_vertex!`](crate::impl_vertex), which derives the attribute
+/// descriptions from the fields of a `#[repr(C)]` struct. The struct must not contain padding
+/// bytes to be uploaded with [`VertexBuffer::create`](super::VertexBuffer::create).
+pub trait Vertex: Copy + 'static {
+    /// Returns the binding description of a per-vertex buffer bound at `binding`.
+    fn binding_description(binding: u32) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription::builder()
+            .binding(binding)
+            .stride(size_of::<Self>() as u32)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build()
+    }
+
+    /// Returns the attribute descriptions of the fields, at consecutive shader locations.
+    fn attribute_descriptions(binding: u32) -> Vec<vk::VertexInputAttributeDescription>;
+}
+
+/// A type that can be a field of a [`Vertex`].
+pub trait VertexAttribute: Copy {
+    /// The format of the attribute in the vertex shader.
+    const FORMAT: vk::Format;
+}
+
+macro_rules! vertex_attributes {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(impl VertexAttribute for $ty {
+            const FORMAT: vk::Format = vk::Format::$format;
+        })*
+    };
+}
+
+vertex_attributes! {
+    f32 => R32_SFLOAT,
+    Vec2 => R32G32_SFLOAT,
+    Vec3 => R32G32B32_SFLOAT,
+    Vec4 => R32G32B32A32_SFLOAT,
+    [f32; 2] => R32G32_SFLOAT,
+    [f32; 3] => R32G32B32_SFLOAT,
+    [f32; 4] => R32G32B32A32_SFLOAT,
+    u32 => R32_UINT,
+    UVec2 => R32G32_UINT,
+    UVec3 => R32G32B32_UINT,
+    UVec4 => R32G32B32A32_UINT,
+    i32 => R32_SINT,
+    IVec2 => R32G32_SINT,
+    IVec3 => R32G32B32_SINT,
+    IVec4 => R32G32B32A32_SINT,
+    [u8; 4] => R8G8B8A8_UNORM,
+}
+
+/// Returns the format of a field, given a function that projects a vertex onto the field.
+#[doc(hidden)]
+pub fn attribute_format<V, A: VertexAttribute>(_field: fn(&V) -> &A) -> vk::Format {
+    A::FORMAT
+}
+
+/// Implements [`Vertex`] for a `#[repr(C)]` struct, with one attribute per listed field at
+/// consecutive shader locations starting at 0.
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Copy, Clone, Debug)]
+/// struct MeshVertex {
+///     position: Vec3,
+///     normal: Vec3,
+///     uv: Vec2,
+/// }
+///
+/// impl_vertex!(MeshVertex { position, normal, uv });
+/// ```
+#[macro_export]
+macro_rules! impl_vertex {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::vulkan::vertex::Vertex for $ty {
+            fn attribute_descriptions(
+                binding: u32,
+            ) -> ::std::vec::Vec<::vulkanalia::vk::VertexInputAttributeDescription> {
+                let mut location = 0;
+                let mut attributes = ::std::vec::Vec::new();
+                $(
+                    attributes.push(::vulkanalia::vk::VertexInputAttributeDescription {
+                        location,
+                        binding,
+                        format: $crate::vulkan::vertex::attribute_format(|v: &$ty| &v.$field),
+                        offset: ::std::mem::offset_of!($ty, $field) as u32,
+                    });
+                    location += 1;
+                )*
+                let _ = location;
+                attributes
+            }
+        }
+    };
+}
+
+/// The vertex input bindings and attributes of a pipeline.
+#[derive(Clone, Debug, Default)]
+pub struct VertexLayout {
+    pub bindings: Vec<vk::VertexInputBindingDescription>,
+    pub attributes: Vec<vk::VertexInputAttributeDescription>,
+}
+
+impl VertexLayout {
+    /// Returns the layout of a single per-vertex buffer of `V` bound at binding 0.
+    pub fn of<V: Vertex>() -> Self {
+        Self {
+            bindings: vec![V::binding_description(0)],
+            attributes: V::attribute_descriptions(0),
+        }
+    }
+}
+
+/// A 2D vertex with a color.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ColorVertex {
+    pub position: Vec2,
+    pub color: Vec3,
+}
+
+impl ColorVertex {
+    pub const fn new(position: Vec2, color: Vec3) -> Self {
+        Self { position, color }
+    }
+}
+
+crate::impl_vertex!(ColorVertex { position, color });
+
+/// A 3D mesh vertex with a normal, texture coordinates and a color.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeshVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub tex_coord: Vec2,
+    pub color: Vec4,
+}
+
+crate::impl_vertex!(MeshVertex {
+    position,
+    normal,
+    tex_coord,
+    color,
+});
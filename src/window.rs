@@ -0,0 +1,675 @@
+//! The application window and the event loop driving the [`Renderer`].
+
+use std::{fmt, time::Duration};
+
+use anyhow::{Error, Result, anyhow};
+use log::*;
+use winit::{
+    application::ApplicationHandler,
+    dpi::{LogicalSize, PhysicalSize, Position, Size},
+    event::{DeviceEvent, DeviceId, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    monitor::{MonitorHandle, VideoModeHandle},
+    window::{CursorGrabMode, Fullscreen, Window as WinitWindow, WindowAttributes, WindowId},
+};
+
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{DEFAULT_DEADZONE, Gamepads};
+use crate::{
+    camera::{CameraController, PerspectiveCamera},
+    input::InputState,
+    renderer::{Renderer, RendererConfig},
+    time::Clock,
+};
+
+/// How often gamepads are polled while [`RenderMode::Reactive`] waits for events.
+#[cfg(feature = "gamepad")]
+const GAMEPAD_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Options used when creating the underlying window of a [`Window`].
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    /// The title shown in the title bar and task bar.
+    pub title: String,
+    /// The initial size of the client area, excluding decorations.
+    pub size: Size,
+    /// The smallest size the client area can be resized to, or `None` for no limit.
+    pub min_size: Option<Size>,
+    /// The largest size the client area can be resized to, or `None` for no limit.
+    pub max_size: Option<Size>,
+    /// Whether the user can resize the window.
+    pub resizable: bool,
+    /// Whether the window has a title bar and borders.
+    pub decorations: bool,
+    /// The initial position of the top left corner of the window, including decorations, or
+    /// `None` to let the window system place it. Ignored on Wayland.
+    pub position: Option<Position>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: String::from("Vulkan-RS"),
+            size: LogicalSize::new(1000.0, 700.0).into(),
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            decorations: true,
+            position: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Returns the attributes the underlying window is created with.
+    fn attributes(&self) -> WindowAttributes {
+        let mut attributes = WinitWindow::default_attributes()
+            .with_title(&self.title)
+            .with_inner_size(self.size)
+            .with_resizable(self.resizable)
+            .with_decorations(self.decorations);
+
+        if let Some(size) = self.min_size {
+            attributes = attributes.with_min_inner_size(size);
+        }
+
+        if let Some(size) = self.max_size {
+            attributes = attributes.with_max_inner_size(size);
+        }
+
+        if let Some(position) = self.position {
+            attributes = attributes.with_position(position);
+        }
+
+        attributes
+    }
+}
+
+/// When a [`Window`] renders frames.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Render frames continuously, as fast as the present mode and frame limiter allow.
+    #[default]
+    Poll,
+    /// Render a frame only when the window system asks for one, when
+    /// [`Window::request_redraw`] is called, or in response to input. Keeps rendering while a
+    /// key or mouse button is held, until input has been consumed by a simulation tick and the
+    /// camera has settled, and after the swapchain is recreated, so camera controllers still
+    /// move smoothly. Suits editors and tools that should not use a CPU core while idle.
+    Reactive,
+}
+
+/// The application update passed to [`Window::run`].
+type Update = dyn FnMut(&mut Window, Duration);
+
+/// A boxed [`Update`], which is not [`Debug`](fmt::Debug) by itself.
+struct UpdateFn(Box<Update>);
+
+impl fmt::Debug for UpdateFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("UpdateFn")
+    }
+}
+
+/// A window that renders until it is closed, and stops rendering while it is minimized or
+/// hidden.
+#[derive(Debug, Default)]
+pub struct Window {
+    config: WindowConfig,
+    renderer_config: RendererConfig,
+    window: Option<WinitWindow>,
+    renderer: Option<Renderer>,
+    input: InputState,
+    cursor_grab: Option<CursorGrabMode>,
+    cursor_hidden: bool,
+    render_mode: RenderMode,
+    fullscreen: FullscreenMode,
+    last_fullscreen: Option<FullscreenMode>,
+    minimized: bool,
+    occluded: bool,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<Gamepads>,
+    #[cfg(feature = "gamepad")]
+    gamepad_deadzone: Option<f32>,
+    camera: PerspectiveCamera,
+    previous_camera: PerspectiveCamera,
+    controller: CameraController,
+    clock: Clock,
+    update: Option<UpdateFn>,
+    error: Option<Error>,
+}
+
+impl Window {
+    /// Creates a window. The underlying window and renderer are created once the event loop starts.
+    pub fn new(config: WindowConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the options used to create the renderer.
+    pub fn with_renderer_config(mut self, renderer_config: RendererConfig) -> Self {
+        self.renderer_config = renderer_config;
+        self
+    }
+
+    /// Sets the controller that moves the camera from the input state, which orbits the origin
+    /// by default. The controller is updated once per frame with the real frame time.
+    pub fn with_camera_controller(mut self, controller: impl Into<CameraController>) -> Self {
+        self.controller = controller.into();
+        self
+    }
+
+    /// Sets the rate of simulation ticks per second, which is 60 by default. Frames render
+    /// between the last two ticks.
+    pub fn with_tick_rate(mut self, tick_rate: f64) -> Self {
+        self.clock = Clock::new(tick_rate);
+        self
+    }
+
+    /// Sets the fraction of gamepad axes around their center that is treated as zero, clamped
+    /// to 0 to [`MAX_DEADZONE`](crate::input::gamepad::MAX_DEADZONE).
+    #[cfg(feature = "gamepad")]
+    pub fn with_gamepad_deadzone(mut self, deadzone: f32) -> Self {
+        self.gamepad_deadzone = Some(deadzone);
+        self
+    }
+
+    /// Grabs or releases the cursor. `Locked` keeps the cursor in place, which suits mouse look
+    /// with [`InputState::mouse_motion`], while `Confined` keeps it inside the window. When the
+    /// platform does not support the requested mode, the other one is used.
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) -> Result<()> {
+        self.cursor_grab = Some(mode);
+        match &self.window {
+            Some(window) => grab_cursor(window, mode),
+            None => Ok(()),
+        }
+    }
+
+    /// Shows or hides the cursor while it is over the window.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_hidden = !visible;
+        if let Some(window) = &self.window {
+            window.set_cursor_visible(visible);
+        }
+    }
+
+    /// Returns the clock that measures frames and runs the simulation ticks.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Returns the clock for modification, such as to pause it or to disable the time control
+    /// hotkeys.
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    /// Returns the keyboard, mouse and gamepad state of the current frame.
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Returns the camera the window renders with.
+    pub fn camera(&self) -> &PerspectiveCamera {
+        &self.camera
+    }
+
+    /// Returns the camera the window renders with for modification.
+    pub fn camera_mut(&mut self) -> &mut PerspectiveCamera {
+        &mut self.camera
+    }
+
+    /// Runs the event loop until the window is closed or rendering fails.
+    ///
+    /// `update` is called once per simulation tick with the tick duration, after the camera
+    /// controller has moved the camera for the frame. It can use the window to read the input
+    /// and to change the camera, cursor, fullscreen mode or render mode. Each press and release
+    /// is seen by a single tick, while the cursor, mouse and scroll movement cover the frame the
+    /// tick runs in.
+    pub fn run(mut self, update: impl FnMut(&mut Window, Duration) + 'static) -> Result<()> {
+        self.update = Some(UpdateFn(Box::new(update)));
+
+        let event_loop = EventLoop::new()?;
+        event_loop.set_control_flow(ControlFlow::Poll);
+        event_loop.run_app(&mut self)?;
+
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Moves the camera, runs the simulation ticks that are due and renders a frame between the
+    /// last two.
+    fn redraw(&mut self) -> Result<()> {
+        let Some(window) = &self.window else {
+            return Ok(());
+        };
+
+        if self.renderer.is_none() {
+            return Ok(());
+        }
+
+        let ticks = self.clock.measure();
+
+        // Camera
+
+        // The camera follows the real frame time rather than the time controls, so it keeps
+        // moving while the simulation is paused or slowed down.
+        self.previous_camera = self.camera;
+        self.controller.update(
+            window,
+            &self.input,
+            &mut self.camera,
+            self.clock.delta().as_secs_f32(),
+        );
+
+        // Simulate
+
+        for _ in 0..ticks {
+            self.tick(self.clock.step());
+        }
+
+        // No ticks consume presses while paused, so drop them instead of applying them on
+        // resume.
+        if self.clock.time_controls().paused() {
+            self.input.clear_presses();
+        }
+
+        self.input.clear_motion();
+
+        // Render
+
+        let (Some(window), Some(renderer)) = (&self.window, &mut self.renderer) else {
+            return Ok(());
+        };
+
+        renderer.update_camera(&self.camera);
+        renderer.set_alpha(self.clock.alpha());
+
+        let time = self.clock.time_controls();
+        renderer.set_time(time.elapsed(), time.delta());
+
+        unsafe { renderer.render(window) }
+    }
+
+    /// Runs one simulation tick: the application update, then consumes the presses.
+    fn tick(&mut self, step: Duration) {
+        if let Some(mut update) = self.update.take() {
+            (update.0)(self, step);
+            self.update = Some(update);
+        }
+
+        self.input.clear_presses();
+    }
+
+    /// Sets when frames are rendered.
+    pub fn with_render_mode(mut self, render_mode: RenderMode) -> Self {
+        self.render_mode = render_mode;
+        self
+    }
+
+    /// Returns when frames are rendered.
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Sets when frames are rendered.
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+        self.request_redraw();
+    }
+
+    /// Requests a frame, which is only needed in [`RenderMode::Reactive`].
+    pub fn request_redraw(&self) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Returns the fullscreen mode.
+    pub fn fullscreen(&self) -> FullscreenMode {
+        self.fullscreen
+    }
+
+    /// Sets the fullscreen mode, applied immediately or once the window is created. The
+    /// swapchain is recreated to match the new size and display mode.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) -> Result<()> {
+        if mode != FullscreenMode::Windowed {
+            self.last_fullscreen = Some(mode);
+        }
+
+        self.fullscreen = mode;
+
+        if let Some(window) = &self.window {
+            window.set_fullscreen(mode.resolve(window)?);
+        }
+
+        if let Some(renderer) = &mut self.renderer {
+            renderer.resize();
+        }
+
+        Ok(())
+    }
+
+    /// Switches between windowed and the last fullscreen mode (borderless by default). Bound to
+    /// Alt+Enter.
+    pub fn toggle_fullscreen(&mut self) -> Result<()> {
+        self.set_fullscreen(match self.fullscreen {
+            FullscreenMode::Windowed => self
+                .last_fullscreen
+                .unwrap_or(FullscreenMode::Borderless { monitor: None }),
+            _ => FullscreenMode::Windowed,
+        })
+    }
+
+    /// Returns whether another frame is needed in [`RenderMode::Reactive`]: while something is
+    /// held down, while presses wait for the next simulation tick, while the camera moved in the
+    /// last frame and may still be settling, or after the swapchain was recreated.
+    fn needs_redraw(&self) -> bool {
+        self.input.any_pressed()
+            || self.input.has_pending()
+            || self.camera != self.previous_camera
+            || self.renderer.as_ref().is_some_and(Renderer::needs_redraw)
+    }
+
+    /// Returns whether rendering is suspended because the window is minimized or hidden.
+    pub fn is_suspended(&self) -> bool {
+        self.minimized || self.occluded
+    }
+
+    /// Updates whether the window is minimized or hidden, suspending or resuming rendering.
+    fn set_visibility(&mut self, minimized: bool, occluded: bool) {
+        let suspended = self.is_suspended();
+        self.minimized = minimized;
+        self.occluded = occluded;
+
+        if !suspended && self.is_suspended() {
+            info!("Suspending rendering while the window is minimized or hidden.");
+        } else if suspended && !self.is_suspended() {
+            info!("Resuming rendering.");
+            self.clock.resume();
+            if let Some(renderer) = &mut self.renderer {
+                renderer.resume();
+            }
+        }
+    }
+
+    /// Records an error, destroys the renderer and stops the event loop.
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: Error) {
+        error!("{error:#}");
+        self.error = Some(error);
+        self.destroy(event_loop);
+    }
+
+    /// Destroys the renderer and stops the event loop.
+    fn destroy(&mut self, event_loop: &ActiveEventLoop) {
+        event_loop.exit();
+        if let Some(mut renderer) = self.renderer.take() {
+            unsafe { renderer.destroy() };
+        }
+    }
+}
+
+impl ApplicationHandler for Window {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+
+        let window = match event_loop.create_window(self.config.attributes()) {
+            Ok(window) => window,
+            Err(error) => return self.fail(event_loop, error.into()),
+        };
+
+        match self.fullscreen.resolve(&window) {
+            Ok(fullscreen) => window.set_fullscreen(fullscreen),
+            Err(error) => warn!("{error:#}"),
+        }
+
+        match unsafe { Renderer::create(&window, &self.renderer_config) } {
+            Ok(renderer) => self.renderer = Some(renderer),
+            Err(error) => return self.fail(event_loop, error),
+        }
+
+        #[cfg(feature = "gamepad")]
+        match Gamepads::new() {
+            Ok(mut gamepads) => {
+                gamepads.set_deadzone(self.gamepad_deadzone.unwrap_or(DEFAULT_DEADZONE));
+                self.gamepads = Some(gamepads);
+            }
+            Err(error) => warn!("{error:#}"),
+        }
+
+        if let Some(mode) = self.cursor_grab
+            && let Err(error) = grab_cursor(&window, mode)
+        {
+            warn!("{error:#}");
+        }
+
+        window.set_cursor_visible(!self.cursor_hidden);
+
+        self.window = Some(window);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        self.input.handle_event(&event);
+
+        // Input can change what is drawn, so it always gets a frame.
+        if self.render_mode == RenderMode::Reactive
+            && matches!(
+                event,
+                WindowEvent::KeyboardInput { .. }
+                    | WindowEvent::MouseInput { .. }
+                    | WindowEvent::CursorMoved { .. }
+                    | WindowEvent::MouseWheel { .. }
+            )
+        {
+            self.request_redraw();
+        }
+
+        match event {
+            // Render a frame if our renderer is not being destroyed or suspended.
+            WindowEvent::RedrawRequested if !event_loop.exiting() && !self.is_suspended() => {
+                if let Err(error) = self.redraw() {
+                    self.fail(event_loop, error);
+                }
+            }
+            // Recreate the swapchain to match the new window size, or suspend rendering while
+            // the window is minimized and the swapchain would be empty.
+            WindowEvent::Resized(size) => {
+                self.set_visibility(size.width == 0 || size.height == 0, self.occluded);
+                if let Some(renderer) = &mut self.renderer {
+                    renderer.resize();
+                }
+            }
+            // Suspend rendering while the window is completely hidden.
+            WindowEvent::Occluded(occluded) => self.set_visibility(self.minimized, occluded),
+            // Toggle fullscreen with Alt+Enter.
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter),
+                        state,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if state.is_pressed() && self.input.modifiers().alt_key() => {
+                if let Err(error) = self.toggle_fullscreen() {
+                    warn!("{error:#}");
+                }
+            }
+            // Apply the time control hotkeys.
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if state.is_pressed() => self.clock.time_controls_mut().handle_key(code),
+            // Destroy our renderer.
+            WindowEvent::CloseRequested => self.destroy(event_loop),
+            _ => {}
+        }
+    }
+
+    fn device_event(&mut self, _: &ActiveEventLoop, _: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Sleep until the next event while suspended.
+        if self.is_suspended() {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
+
+        // Gamepad events do not wake the event loop, so they are polled here, and periodically
+        // below while waiting.
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepads) = &mut self.gamepads {
+            gamepads.poll(&mut self.input);
+        }
+
+        match self.render_mode {
+            // Request a redraw when all events were processed.
+            RenderMode::Poll => {
+                event_loop.set_control_flow(ControlFlow::Poll);
+                self.request_redraw();
+            }
+            // Sleep until the next event unless a frame is still needed. The time spent idle is
+            // not simulated.
+            RenderMode::Reactive => {
+                event_loop.set_control_flow(ControlFlow::Wait);
+                if self.needs_redraw() {
+                    self.request_redraw();
+                } else {
+                    self.clock.resume();
+                }
+
+                #[cfg(feature = "gamepad")]
+                if self.gamepads.is_some() {
+                    event_loop.set_control_flow(ControlFlow::wait_duration(GAMEPAD_POLL_INTERVAL));
+                }
+            }
+        }
+    }
+}
+
+/// Grabs or releases the cursor of a window, falling back to the other grab mode when the
+/// platform does not support the requested one (`Locked` on Windows and X11, `Confined` on
+/// macOS).
+pub(crate) fn grab_cursor(window: &WinitWindow, mode: CursorGrabMode) -> Result<()> {
+    let fallback = match mode {
+        CursorGrabMode::Confined => CursorGrabMode::Locked,
+        CursorGrabMode::Locked => CursorGrabMode::Confined,
+        CursorGrabMode::None => CursorGrabMode::None,
+    };
+
+    window
+        .set_cursor_grab(mode)
+        .or_else(|_| window.set_cursor_grab(fallback))
+        .map_err(|e| anyhow!("Failed to set cursor grab to {mode:?}: {e}"))
+}
+
+//================================================
+// Fullscreen
+//================================================
+
+/// How a [`Window`] covers a monitor.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// A regular window.
+    #[default]
+    Windowed,
+    /// A borderless window covering a monitor at its current video mode.
+    Borderless {
+        /// The index of the monitor in [`ActiveEventLoop::available_monitors`] order, or `None`
+        /// for the monitor the window is on.
+        monitor: Option<usize>,
+    },
+    /// Exclusive fullscreen, which switches the monitor to a video mode.
+    Exclusive {
+        /// The index of the monitor in [`ActiveEventLoop::available_monitors`] order, or `None`
+        /// for the monitor the window is on.
+        monitor: Option<usize>,
+        /// The resolution of the video mode, or `None` for the largest.
+        size: Option<PhysicalSize<u32>>,
+        /// The refresh rate of the video mode in millihertz, or `None` for the highest. The
+        /// closest available rate is used.
+        refresh_rate_millihertz: Option<u32>,
+    },
+}
+
+impl FullscreenMode {
+    /// Resolves the monitor and video mode for a window.
+    fn resolve(&self, window: &WinitWindow) -> Result<Option<Fullscreen>> {
+        match *self {
+            Self::Windowed => Ok(None),
+            Self::Borderless { monitor } => Ok(Some(Fullscreen::Borderless(Some(select_monitor(
+                window, monitor,
+            )?)))),
+            Self::Exclusive {
+                monitor,
+                size,
+                refresh_rate_millihertz,
+            } => {
+                let monitor = select_monitor(window, monitor)?;
+                let video_mode = select_video_mode(&monitor, size, refresh_rate_millihertz)?;
+                info!(
+                    "Selected exclusive fullscreen video mode {}x{} at {:.2} Hz.",
+                    video_mode.size().width,
+                    video_mode.size().height,
+                    video_mode.refresh_rate_millihertz() as f32 / 1000.0,
+                );
+                Ok(Some(Fullscreen::Exclusive(video_mode)))
+            }
+        }
+    }
+}
+
+/// Returns a monitor by index, or the monitor the window is on.
+fn select_monitor(window: &WinitWindow, index: Option<usize>) -> Result<MonitorHandle> {
+    match index {
+        Some(index) => window
+            .available_monitors()
+            .nth(index)
+            .ok_or_else(|| anyhow!("No monitor with index {index}.")),
+        None => window
+            .current_monitor()
+            .or_else(|| window.primary_monitor())
+            .or_else(|| window.available_monitors().next())
+            .ok_or_else(|| anyhow!("No monitor available for fullscreen.")),
+    }
+}
+
+/// Returns the video mode of a monitor that best matches a size and refresh rate, preferring
+/// larger sizes, higher bit depths and higher refresh rates.
+fn select_video_mode(
+    monitor: &MonitorHandle,
+    size: Option<PhysicalSize<u32>>,
+    refresh_rate_millihertz: Option<u32>,
+) -> Result<VideoModeHandle> {
+    monitor
+        .video_modes()
+        .filter(|m| size.is_none_or(|s| m.size() == s))
+        .max_by_key(|m| {
+            let area = m.size().width as u64 * m.size().height as u64;
+            let refresh_rate = match refresh_rate_millihertz {
+                Some(r) => u32::MAX - m.refresh_rate_millihertz().abs_diff(r),
+                None => m.refresh_rate_millihertz(),
+            };
+            (area, refresh_rate, m.bit_depth())
+        })
+        .ok_or_else(|| match size {
+            Some(s) => anyhow!("No video mode with size {}x{}.", s.width, s.height),
+            None => anyhow!("No video modes available."),
+        })
+}